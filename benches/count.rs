@@ -0,0 +1,40 @@
+/* crate use */
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+/* local use */
+use crazyqc::*;
+
+const SEQ_LEN: usize = 1 << 20;
+
+fn sequence() -> Vec<u8> {
+    let mut state: u64 = 42;
+
+    (0..SEQ_LEN)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+
+            b"ACGTN"[(state % 5) as usize]
+        })
+        .collect()
+}
+
+fn count(c: &mut Criterion) {
+    let sequence = sequence();
+
+    let mut g = c.benchmark_group("count");
+    g.throughput(Throughput::Bytes(SEQ_LEN as u64));
+
+    g.bench_function("scalar", |b| {
+        b.iter(|| count::count_scalar(black_box(&sequence)))
+    });
+    g.bench_function("dispatch", |b| {
+        b.iter(|| count::count(black_box(&sequence)))
+    });
+
+    g.finish();
+}
+
+criterion_group!(benches, count);
+criterion_main!(benches);
@@ -0,0 +1,99 @@
+/* std use */
+use std::io::Write;
+
+/* crate use */
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use rayon::prelude::*;
+
+/* local use */
+use crazyqc::*;
+
+const NB_READS: usize = 200_000;
+const READ_LEN: usize = 150;
+
+fn create_fastq_file() -> (tempfile::NamedTempFile, u64) {
+    let mut tmp_file = tempfile::NamedTempFile::new().unwrap();
+
+    let mut state: u64 = 42;
+    let mut sequence = vec![b'A'; READ_LEN];
+    {
+        let mut writer = std::io::BufWriter::new(tmp_file.as_file_mut());
+        for i in 0..NB_READS {
+            for nuc in sequence.iter_mut() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *nuc = b"ACGT"[(state & 0b11) as usize];
+            }
+
+            writeln!(writer, "@read{}", i).unwrap();
+            writer.write_all(&sequence).unwrap();
+            writeln!(writer, "\n+").unwrap();
+            writer.write_all(&[b'I'; READ_LEN]).unwrap();
+            writeln!(writer).unwrap();
+        }
+    }
+
+    let size = tmp_file.as_file().metadata().unwrap().len();
+
+    (tmp_file, size)
+}
+
+fn template() -> metric::MetricSet<input::fastq::Record> {
+    metric::MetricSet::new()
+        .with(metric::Composition::new())
+        .with(metric::Gc::new())
+        .with(metric::Length::new())
+        .with(metric::Quality::new())
+}
+
+/// Previous design, each record is allocate and send to rayon by par_bridge
+fn per_record(
+    path: &str,
+    template: &metric::MetricSet<input::fastq::Record>,
+) -> Vec<metric::Field> {
+    let reader = input::Fastq::new(vec![path.to_string()], 8192).unwrap();
+
+    reader
+        .par_bridge()
+        .fold(
+            || template.empty(),
+            |mut set, (_, record)| {
+                set.observe(&record.unwrap());
+                set
+            },
+        )
+        .reduce(
+            || template.empty(),
+            |mut a, b| {
+                a.merge(&b);
+                a
+            },
+        )
+        .finalize()
+}
+
+/// Batch design
+fn batch(path: &str, template: &metric::MetricSet<input::fastq::Record>) -> Vec<metric::Field> {
+    let reader = input::Fastq::new(vec![path.to_string()], 8192).unwrap();
+
+    metric::compute(reader, template, 1)[0].finalize()
+}
+
+fn pipeline(c: &mut Criterion) {
+    let (file, size) = create_fastq_file();
+    let path = file.path().to_str().unwrap();
+    let template = template();
+
+    let mut g = c.benchmark_group("pipeline");
+    g.sample_size(10);
+    g.throughput(Throughput::Bytes(size));
+
+    g.bench_function("per_record", |b| b.iter(|| per_record(path, &template)));
+    g.bench_function("batch", |b| b.iter(|| batch(path, &template)));
+
+    g.finish();
+}
+
+criterion_group!(benches, pipeline);
+criterion_main!(benches);
@@ -0,0 +1,6 @@
+//! Build script, declare cfg use by tarpaulin so rustc doesn't warn on unexpected cfg
+
+fn main() {
+    // Ignored by cargo without check-cfg support
+    println!("cargo:rustc-check-cfg=cfg(tarpaulin_include)");
+}
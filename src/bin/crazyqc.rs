@@ -1,6 +1,6 @@
 /* crate use */
+use anyhow::Context;
 use clap::Parser;
-use rayon::prelude::*;
 
 /* local use */
 use crazyqc::*;
@@ -25,86 +25,253 @@ fn main() -> anyhow::Result<()> {
         cli::set_nb_threads(threads);
     }
 
-    let buffer_size: usize = if let Some(b) = params.buffer_size {
-        b
-    } else {
-        8192
-    };
+    let buffer_size = params.buffer_size.unwrap_or(8192);
+    let max_position = params.max_position.unwrap_or(500);
+
+    if params.tee.as_deref() == Some(input::stream::STDIN) && params.output.is_none() {
+        return Err(error::Error::TeeAndOutputOnStdout.into());
+    }
 
     let mut output: Box<dyn std::io::Write> = if let Some(o) = params.output {
-        Box::new(std::io::BufWriter::new(std::fs::File::open(o)?))
+        Box::new(std::io::BufWriter::new(std::fs::File::create(o)?))
     } else {
         Box::new(std::io::stdout())
     };
 
-    log::info!("Start read fastq");
-    let reader = input::Fastq::new(params.fastq, buffer_size)?;
-
-    let (at, gc, other, nb_reads) = reader
-        .par_bridge()
-        .map(input::fastq::worker)
-        .reduce(reduce_identity, reduce);
-
-    let total = (at + gc + other) as f64;
-    log::info!("End read fastq");
-
-    writeln!(output, "type,at,gc,other,mean_length,n")?;
-    writeln!(
-        output,
-        "fastq,{:.4},{:.4},{:.4},{:.2},{}",
-        (at as f64 / total) * 100.0,
-        (gc as f64 / total) * 100.0,
-        (other as f64 / total) * 100.0,
-        total / nb_reads as f64,
-        nb_reads
-    )?;
+    /* Dispatch inputs by format */
+    let mut fastqs = sources(params.fastq);
+    let mut fastas = sources(params.fasta.unwrap_or_default());
+    let mut bams = sources(params.bam.unwrap_or_default());
+    let mut sams = sources(params.sam.unwrap_or_default());
+    let mut crams = sources(params.cram.unwrap_or_default());
+    for path in params.inputs {
+        match input::detect::detect(path.into())? {
+            (input::Format::Fastq, source) => fastqs.push(source),
+            (input::Format::Fasta, source) => fastas.push(source),
+            (input::Format::Bam, source) => bams.push(source),
+            (input::Format::Sam, source) => sams.push(source),
+            (input::Format::Cram, source) => crams.push(source),
+        }
+    }
+
+    /* Copy input in tee path */
+    if let Some(path) = params.tee {
+        let mut inputs: Vec<&mut input::stream::Source> = fastqs
+            .iter_mut()
+            .chain(&mut fastas)
+            .chain(&mut bams)
+            .chain(&mut sams)
+            .chain(&mut crams)
+            .collect();
+        if inputs.len() != 1 {
+            return Err(error::Error::TeeNeedOneInput {
+                nb_inputs: inputs.len(),
+            }
+            .into());
+        }
+
+        let copy: Box<dyn std::io::Write + Send> = if path == input::stream::STDIN {
+            Box::new(std::io::stdout())
+        } else {
+            Box::new(std::io::BufWriter::new(std::fs::File::create(path)?))
+        };
+        inputs[0].tee(copy)?;
+    }
+
+    let fastqs_path = paths(&fastqs);
+    let fastas_path = paths(&fastas);
+    let bams_path = paths(&bams);
+    let sams_path = paths(&sams);
+    let crams_path = paths(&crams);
+
+    let targets = if let Some(path) = params.targets {
+        Some(std::sync::Arc::new(
+            input::bed::read_targets(&path)
+                .with_context(|| error::Error::BedOpenError { path: path.clone() })?,
+        ))
+    } else {
+        None
+    };
+
+    let regions = params
+        .region
+        .iter()
+        .map(|region| input::region::parse(region))
+        .collect::<anyhow::Result<Vec<input::bed::Target>>>()?;
+    if !regions.is_empty() && (!sams_path.is_empty() || !crams_path.is_empty()) {
+        log::warn!("Regions are use only for bam inputs, sam and cram are read completely");
+    }
+
+    let mut report = report::Report::new();
+
+    /* Run count of fastq file if option is set */
+    if !fastqs_path.is_empty() {
+        log::info!("Start read fastq");
+        let reader = input::Fastq::new(fastqs, buffer_size)?;
+
+        let template = metric::MetricSet::new()
+            .with(metric::Composition::new())
+            .with(metric::Gc::new())
+            .with(metric::Length::new())
+            .with(metric::Quality::new())
+            .with(metric::Position::new(max_position));
+        let sets = metric::compute(reader, &template, fastqs_path.len());
+        log::info!("End read fastq");
+
+        report.add_inputs("fastq", &fastqs_path, &sets, &template);
+    }
+
+    /* Run count of fasta file if option is set */
+    if !fastas_path.is_empty() {
+        log::info!("Start read fasta");
+        let reader = input::batch::Batched::new(input::Fasta::new(fastas, buffer_size)?);
+
+        let template = metric::MetricSet::new()
+            .with(metric::Composition::new())
+            .with(metric::Gc::new())
+            .with(metric::Length::new());
+        let sets = metric::compute(reader, &template, fastas_path.len());
+        log::info!("End read fasta");
+
+        report.add_inputs("fasta", &fastas_path, &sets, &template);
+    }
 
     /* Run count of bam file if option is set */
-    if let Some(bams_path) = params.bam {
+    if !bams_path.is_empty() {
         log::info!("Start read bam");
-        let reader = input::Bam::new(bams_path, buffer_size)?;
+        let mut reader = input::Bam::new(bams, buffer_size)?;
+        if !regions.is_empty() {
+            reader = reader.with_regions(regions)?;
+        }
+        if params.coverage || targets.is_some() {
+            reader = reader.with_coverage();
+        }
+        if let Some(targets) = targets.as_ref() {
+            reader = reader.with_targets(targets.clone());
+        }
 
-        let (at, gc, other, nb_reads) = reader
-            .par_bridge()
-            .map(input::bam::worker)
-            .reduce(reduce_identity, reduce);
+        let mut template =
+            alignment_template(max_position, reader.dictionaries(), targets.as_ref());
+        if let Some(depths) = reader.depths() {
+            let coverage = metric::Coverage::new(depths);
+            template = template.with(if targets.is_some() {
+                coverage.with_targets()
+            } else {
+                coverage
+            });
+        }
+        let sets = metric::compute(reader, &template, bams_path.len());
         log::info!("End read bam");
 
-        let total = (at + gc + other) as f64;
+        report.add_inputs("bam", &bams_path, &sets, &template);
+    }
+
+    /* Run count of sam file if option is set */
+    if !sams_path.is_empty() {
+        log::info!("Start read sam");
+        let reader = input::Sam::new(sams, buffer_size)?;
+
+        let template = alignment_template(max_position, reader.dictionaries(), targets.as_ref());
+        let reader = input::batch::Batched::new(reader);
+        let sets = metric::compute(reader, &template, sams_path.len());
+        log::info!("End read sam");
+
+        report.add_inputs("sam", &sams_path, &sets, &template);
+    }
 
-        writeln!(
-            output,
-            "bam,{:.4},{:.4},{:.4},{:.2},{}",
-            at as f64 / total * 100.0,
-            gc as f64 / total * 100.0,
-            other as f64 / total * 100.0,
-            total / nb_reads as f64,
-            nb_reads
+    /* Run count of cram file if option is set */
+    if !crams_path.is_empty() {
+        log::info!("Start read cram");
+        let reference = if let Some(path) = params.reference {
+            input::cram::read_reference(&path)
+                .with_context(|| error::Error::ReferenceOpenError { path: path.clone() })?
+        } else {
+            Vec::new()
+        };
+        let reader = input::Cram::new(crams, reference, buffer_size)?;
+
+        let template = alignment_template(max_position, reader.dictionaries(), targets.as_ref());
+        let reader = input::batch::Batched::new(reader);
+        let sets = metric::compute(reader, &template, crams_path.len());
+        log::info!("End read cram");
+
+        report.add_inputs("cram", &crams_path, &sets, &template);
+    }
+
+    output::write(
+        &mut output,
+        &report,
+        params.format.unwrap_or(output::Format::Csv),
+    )?;
+
+    if let Some(path) = params.html {
+        let mut html = std::io::BufWriter::new(std::fs::File::create(path)?);
+        output::html::write(&mut html, &report)?;
+    }
+
+    if let Some(path) = params.idxstats {
+        let mut idxstats = std::io::BufWriter::new(std::fs::File::create(path)?);
+        output::table::write(&mut idxstats, &report, "idxstats", &output::table::IDXSTATS)?;
+    }
+
+    if let Some(path) = params.insert_size {
+        let mut insert_size = std::io::BufWriter::new(std::fs::File::create(path)?);
+        output::table::write(
+            &mut insert_size,
+            &report,
+            "insert_size_by_read_group",
+            &output::table::INSERT_SIZE,
         )?;
     }
 
-    Ok(())
-}
+    if let Some(path) = params.target_coverage {
+        let mut target_coverage = std::io::BufWriter::new(std::fs::File::create(path)?);
+        output::table::write(
+            &mut target_coverage,
+            &report,
+            "target_coverage_by_target",
+            &output::table::TARGET_COVERAGE,
+        )?;
+    }
 
-fn reduce(a: (u64, u64, u64, u64), b: (u64, u64, u64, u64)) -> (u64, u64, u64, u64) {
-    (a.0 + b.0, a.1 + b.1, a.2 + b.2, a.3 + b.3)
-}
+    if let Some(directory) = params.multiqc {
+        output::multiqc::write(directory, &report)?;
+    }
 
-fn reduce_identity() -> (u64, u64, u64, u64) {
-    (0, 0, 0, 0)
+    Ok(())
 }
 
-#[cfg(test)]
-mod t {
-    use super::*;
+/// Metrics compute on alignment file (bam, sam, cram)
+fn alignment_template(
+    max_position: usize,
+    dictionaries: input::Dictionaries,
+    targets: Option<&std::sync::Arc<input::bed::Targets>>,
+) -> metric::MetricSet<noodles::bam::Record> {
+    let template = metric::MetricSet::new()
+        .with(metric::Composition::new())
+        .with(metric::Gc::new())
+        .with(metric::Length::new())
+        .with(metric::Position::new(max_position))
+        .with(metric::Flagstat::new())
+        .with(metric::Mapq::new())
+        .with(metric::InsertSize::new())
+        .with(metric::Idxstats::new(dictionaries.clone()));
 
-    #[test]
-    fn reduce_() {
-        assert_eq!(reduce((1, 1, 1, 1), (2, 2, 2, 2)), (3, 3, 3, 3));
+    match targets {
+        Some(targets) => template.with(metric::OnTarget::new(targets.clone(), dictionaries)),
+        None => template,
     }
+}
 
-    #[test]
-    fn reduce_identity_() {
-        assert_eq!(reduce_identity(), (0, 0, 0, 0))
-    }
+/// Inputs give by path
+fn sources(paths: Vec<String>) -> Vec<input::stream::Source> {
+    paths.into_iter().map(Into::into).collect()
+}
+
+/// Path of each input
+fn paths(sources: &[input::stream::Source]) -> Vec<String> {
+    sources
+        .iter()
+        .map(|source| source.path().to_string())
+        .collect()
 }
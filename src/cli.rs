@@ -9,22 +9,88 @@
     override_help = "A crazily fast tools to compute small set of sequence quality control"
 )]
 pub struct Command {
+    /// Inputs, format (fastq, fasta, sam, bam, cram) is detect from content
+    pub inputs: Vec<String>,
+
     /// Fastq input
     #[clap(short = 'q', long = "fastq")]
     pub fastq: Vec<String>,
 
+    /// Fasta input
+    #[clap(short = 'a', long = "fasta")]
+    pub fasta: Option<Vec<String>>,
+
     /// Bam input
     #[clap(short = 'b', long = "bam")]
     pub bam: Option<Vec<String>>,
 
+    /// Sam input
+    #[clap(short = 's', long = "sam")]
+    pub sam: Option<Vec<String>>,
+
+    /// Cram input
+    #[clap(short = 'c', long = "cram")]
+    pub cram: Option<Vec<String>>,
+
+    /// Fasta reference use to decode cram, without it only unmapped reads of cram can be read
+    #[clap(short = 'r', long = "reference")]
+    pub reference: Option<String>,
+
     /// Path where result will be write, default: stdout
     #[clap(short = 'o', long = "output")]
     pub output: Option<String>,
 
+    /// Path where input will be copy unchanged, `-` is stdout, work only with one input
+    #[clap(long = "tee")]
+    pub tee: Option<String>,
+
+    /// Path where a self-contained html report will be write
+    #[clap(long = "html")]
+    pub html: Option<String>,
+
+    /// Path where reads count by reference sequence of alignment inputs will be write, in tsv
+    #[clap(long = "idxstats")]
+    pub idxstats: Option<String>,
+
+    /// Path where insert size by read group and orientation of alignment inputs will be write, in
+    /// tsv
+    #[clap(long = "insert-size")]
+    pub insert_size: Option<String>,
+
+    /// Bed of target regions, alignments are classify as on, near or off target and coverage of
+    /// bam input by target is compute, bam must be sorted by coordinate
+    #[clap(long = "targets")]
+    pub targets: Option<String>,
+
+    /// Region (chr1:1-1000000) of bam input to read, can be repeat, bam must have an index (.bai
+    /// or .csi), other inputs are read completely
+    #[clap(long = "region", multiple_occurrences(true), number_of_values(1))]
+    pub region: Vec<String>,
+
+    /// Path where coverage by target of bam inputs will be write, in tsv
+    #[clap(long = "target-coverage")]
+    pub target_coverage: Option<String>,
+
+    /// Directory where MultiQC custom content files will be write
+    #[clap(short = 'm', long = "multiqc")]
+    pub multiqc: Option<String>,
+
+    /// Format of result, default: csv
+    #[clap(short = 'f', long = "format", arg_enum)]
+    pub format: Option<crate::output::Format>,
+
     /// Size of reading buffer in bytes, default: 8192
     #[clap(short = 'B', long = "buffer-size")]
     pub buffer_size: Option<usize>,
 
+    /// Compute depth of coverage of bam input, bam must be sorted by coordinate
+    #[clap(long = "coverage")]
+    pub coverage: bool,
+
+    /// Maximal number of position tracked by per position profile, default: 500
+    #[clap(short = 'p', long = "max-position")]
+    pub max_position: Option<usize>,
+
     /// Number of thread use by crazyqc, 0 use all avaible core, default: 0
     #[clap(short = 't', long = "threads")]
     pub threads: Option<usize>,
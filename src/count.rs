@@ -0,0 +1,267 @@
+//! Count nucleotides of a sequence in one pass
+//!
+//! On x86_64 AVX2 or SSE2 is used if cpu support it (runtime detection), other architectures use
+//! scalar version. All versions are case insensitive.
+//!
+//! Characters other than A, C, G, T and N are split in IUPAC ambiguity codes (R, Y, S, W, K, M,
+//! B, D, H, V) and invalid characters, by a scalar pass only if sequence contains such
+//! characters.
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+
+/// Number of each nucleotides in a sequence
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub a: u64,
+    pub c: u64,
+    pub g: u64,
+    pub t: u64,
+    pub n: u64,
+    pub iupac: u64,
+    pub invalid: u64,
+}
+
+impl Counts {
+    /// Number of A, C, G and T
+    pub fn acgt(&self) -> u64 {
+        self.a + self.c + self.g + self.t
+    }
+
+    /// Number of C and G
+    pub fn gc(&self) -> u64 {
+        self.c + self.g
+    }
+
+    /// Number of characters other than A, C, G and T
+    pub fn other(&self) -> u64 {
+        self.n + self.iupac + self.invalid
+    }
+
+    /// Total number of characters
+    pub fn total(&self) -> u64 {
+        self.acgt() + self.other()
+    }
+}
+
+impl std::ops::AddAssign for Counts {
+    fn add_assign(&mut self, other: Self) {
+        self.a += other.a;
+        self.c += other.c;
+        self.g += other.g;
+        self.t += other.t;
+        self.n += other.n;
+        self.iupac += other.iupac;
+        self.invalid += other.invalid;
+    }
+}
+
+/// Count nucleotides of sequence with fastest available implementation
+pub fn count(sequence: &[u8]) -> Counts {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // Safety: avx2 support is check
+            return unsafe { count_avx2(sequence) };
+        } else if is_x86_feature_detected!("sse2") {
+            // Safety: sse2 support is check
+            return unsafe { count_sse2(sequence) };
+        }
+    }
+
+    count_scalar(sequence)
+}
+
+/// Return true if nuc is an IUPAC ambiguity code other than N
+pub fn is_iupac(nuc: u8) -> bool {
+    matches!(
+        nuc | 0x20,
+        b'r' | b'y' | b's' | b'w' | b'k' | b'm' | b'b' | b'd' | b'h' | b'v'
+    )
+}
+
+/// Count nucleotides of sequence, scalar version
+pub fn count_scalar(sequence: &[u8]) -> Counts {
+    let mut counts = Counts::default();
+
+    for nuc in sequence {
+        match nuc {
+            b'a' | b'A' => counts.a += 1,
+            b'c' | b'C' => counts.c += 1,
+            b'g' | b'G' => counts.g += 1,
+            b't' | b'T' => counts.t += 1,
+            b'n' | b'N' => counts.n += 1,
+            n if is_iupac(*n) => counts.iupac += 1,
+            _ => counts.invalid += 1,
+        }
+    }
+
+    counts
+}
+
+/// Split characters that aren't A, C, G, T or N in IUPAC and invalid
+#[cfg(target_arch = "x86_64")]
+fn split_other(counts: &mut Counts, sequence: &[u8], nb_other: u64) {
+    if nb_other != 0 {
+        counts.iupac = sequence.iter().filter(|n| is_iupac(**n)).count() as u64;
+        counts.invalid = nb_other - counts.iupac;
+    }
+}
+
+/// Count nucleotides of sequence, sse2 version
+///
+/// # Safety
+/// Cpu must support sse2
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+pub unsafe fn count_sse2(sequence: &[u8]) -> Counts {
+    use std::arch::x86_64::*;
+
+    let mut counts = Counts::default();
+
+    let lower = _mm_set1_epi8(0x20);
+    let a = _mm_set1_epi8(b'a' as i8);
+    let c = _mm_set1_epi8(b'c' as i8);
+    let g = _mm_set1_epi8(b'g' as i8);
+    let t = _mm_set1_epi8(b't' as i8);
+    let n = _mm_set1_epi8(b'n' as i8);
+
+    let mut chunks = sequence.chunks_exact(16);
+    for chunk in &mut chunks {
+        let v = _mm_or_si128(_mm_loadu_si128(chunk.as_ptr() as *const __m128i), lower);
+
+        counts.a += _mm_movemask_epi8(_mm_cmpeq_epi8(v, a)).count_ones() as u64;
+        counts.c += _mm_movemask_epi8(_mm_cmpeq_epi8(v, c)).count_ones() as u64;
+        counts.g += _mm_movemask_epi8(_mm_cmpeq_epi8(v, g)).count_ones() as u64;
+        counts.t += _mm_movemask_epi8(_mm_cmpeq_epi8(v, t)).count_ones() as u64;
+        counts.n += _mm_movemask_epi8(_mm_cmpeq_epi8(v, n)).count_ones() as u64;
+    }
+    let nb_other = (sequence.len() - chunks.remainder().len()) as u64 - counts.acgt() - counts.n;
+    split_other(
+        &mut counts,
+        &sequence[..sequence.len() - chunks.remainder().len()],
+        nb_other,
+    );
+
+    counts += count_scalar(chunks.remainder());
+
+    counts
+}
+
+/// Count nucleotides of sequence, avx2 version
+///
+/// # Safety
+/// Cpu must support avx2
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+pub unsafe fn count_avx2(sequence: &[u8]) -> Counts {
+    use std::arch::x86_64::*;
+
+    let mut counts = Counts::default();
+
+    let lower = _mm256_set1_epi8(0x20);
+    let a = _mm256_set1_epi8(b'a' as i8);
+    let c = _mm256_set1_epi8(b'c' as i8);
+    let g = _mm256_set1_epi8(b'g' as i8);
+    let t = _mm256_set1_epi8(b't' as i8);
+    let n = _mm256_set1_epi8(b'n' as i8);
+
+    let mut chunks = sequence.chunks_exact(32);
+    for chunk in &mut chunks {
+        let v = _mm256_or_si256(_mm256_loadu_si256(chunk.as_ptr() as *const __m256i), lower);
+
+        counts.a += _mm256_movemask_epi8(_mm256_cmpeq_epi8(v, a)).count_ones() as u64;
+        counts.c += _mm256_movemask_epi8(_mm256_cmpeq_epi8(v, c)).count_ones() as u64;
+        counts.g += _mm256_movemask_epi8(_mm256_cmpeq_epi8(v, g)).count_ones() as u64;
+        counts.t += _mm256_movemask_epi8(_mm256_cmpeq_epi8(v, t)).count_ones() as u64;
+        counts.n += _mm256_movemask_epi8(_mm256_cmpeq_epi8(v, n)).count_ones() as u64;
+    }
+    let nb_other = (sequence.len() - chunks.remainder().len()) as u64 - counts.acgt() - counts.n;
+    split_other(
+        &mut counts,
+        &sequence[..sequence.len() - chunks.remainder().len()],
+        nb_other,
+    );
+
+    counts += count_scalar(chunks.remainder());
+
+    counts
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    /// Generate a random sequence, half of bytes are nucleotides or IUPAC other are random bytes
+    fn random_sequence(state: &mut u64, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                *state ^= *state << 13;
+                *state ^= *state >> 7;
+                *state ^= *state << 17;
+
+                if *state & 0x100 == 0 {
+                    b"ACGTNacgtnRYSWKMBDHVryswkmbdhv"[(*state % 30) as usize]
+                } else {
+                    (*state >> 32) as u8
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scalar() {
+        assert_eq!(
+            count_scalar(b"ACGTNacgtnRYbv-.*"),
+            Counts {
+                a: 2,
+                c: 2,
+                g: 2,
+                t: 2,
+                n: 2,
+                iupac: 4,
+                invalid: 3
+            }
+        );
+        assert_eq!(count_scalar(b""), Counts::default());
+    }
+
+    #[test]
+    fn counts() {
+        let mut counts = count(b"ACGTNNRY");
+        assert_eq!(counts.acgt(), 4);
+        assert_eq!(counts.gc(), 2);
+        assert_eq!(counts.other(), 4);
+        assert_eq!(counts.total(), 8);
+
+        counts += count(b"GG%");
+        assert_eq!(counts.g, 3);
+        assert_eq!(counts.iupac, 2);
+        assert_eq!(counts.invalid, 1);
+    }
+
+    #[test]
+    fn random() {
+        let mut state = 42;
+
+        for len in (0..300).chain([1000, 4096, 10_007]) {
+            let sequence = random_sequence(&mut state, len);
+            let expected = count_scalar(&sequence);
+
+            assert_eq!(count(&sequence), expected);
+
+            #[cfg(target_arch = "x86_64")]
+            {
+                if is_x86_feature_detected!("sse2") {
+                    assert_eq!(unsafe { count_sse2(&sequence) }, expected);
+                }
+                if is_x86_feature_detected!("avx2") {
+                    assert_eq!(unsafe { count_avx2(&sequence) }, expected);
+                }
+            }
+        }
+    }
+}
@@ -11,6 +11,14 @@ pub enum Error {
     #[error("Error durring fastq record parsing")]
     FastqParsingError,
 
+    /// Failled to open fasta file
+    #[error("Can't open fasta file {path}")]
+    FastaOpenError { path: String },
+
+    /// Fasta parsing error
+    #[error("Error durring fasta record parsing")]
+    FastaParsingError,
+
     /// Failled to open bam file
     #[error("Can't open bam file {path}")]
     BamOpenError { path: String },
@@ -18,4 +26,52 @@ pub enum Error {
     /// Bam parsing error
     #[error("Error durring bam record parsing")]
     BamParsingError,
+
+    /// Failled to open sam file
+    #[error("Can't open sam file {path}")]
+    SamOpenError { path: String },
+
+    /// Sam parsing error
+    #[error("Error durring sam record parsing")]
+    SamParsingError,
+
+    /// Failled to open cram file
+    #[error("Can't open cram file {path}")]
+    CramOpenError { path: String },
+
+    /// Cram parsing error
+    #[error("Error durring cram record parsing")]
+    CramParsingError,
+
+    /// Failled to read reference
+    #[error("Can't read reference file {path}")]
+    ReferenceOpenError { path: String },
+
+    /// Failled to read targets
+    #[error("Can't read targets file {path}")]
+    BedOpenError { path: String },
+
+    /// Bed parsing error
+    #[error("Error durring bed parsing at line {line}")]
+    BedParsingError { line: usize },
+
+    /// Failled to find index of bam
+    #[error("Can't find index (.bai or .csi) of bam file {path}")]
+    BamIndexNotFound { path: String },
+
+    /// Region parsing error
+    #[error("Error durring parsing of region {region}")]
+    RegionParsingError { region: String },
+
+    /// Failled to detect format of file
+    #[error("Can't detect format of file {path}")]
+    UnknownFormat { path: String },
+
+    /// Tee mode work only with one input
+    #[error("Tee mode need exactly one input, {nb_inputs} given")]
+    TeeNeedOneInput { nb_inputs: usize },
+
+    /// Tee and result can't be write both on stdout
+    #[error("Tee and result can't be write both on stdout, set option output")]
+    TeeAndOutputOnStdout,
 }
@@ -7,109 +7,366 @@ use anyhow::Context;
 
 /* project use */
 use crate::error;
+use crate::input::batch;
+use crate::input::bed;
+use crate::input::bgzf;
+use crate::input::coverage;
+use crate::input::dictionary;
+use crate::input::region;
+use crate::input::stream;
+
+/// Bam reader, bgzf blocks are decompressed in parallel
+type Reader = noodles::bam::Reader<bgzf::ParallelReader<std::io::BufReader<stream::Stream>>>;
+
+/// Seekable bam reader, use to read only regions
+type IndexedReader = noodles::bam::Reader<noodles::bgzf::Reader<std::fs::File>>;
+
+/// Read records of regions, only chunks of index overlapping regions are read
+struct Query {
+    reader: IndexedReader,
+    chunks: std::vec::IntoIter<region::Chunk>,
+    end: Option<noodles::bgzf::VirtualPosition>,
+    intervals: Vec<region::Interval>,
+}
+
+impl Query {
+    fn new(
+        reader: IndexedReader,
+        chunks: Vec<region::Chunk>,
+        intervals: Vec<region::Interval>,
+    ) -> std::io::Result<Self> {
+        let mut query = Self {
+            reader,
+            chunks: chunks.into_iter(),
+            end: None,
+            intervals,
+        };
+        query.next_chunk()?;
+
+        Ok(query)
+    }
+
+    /// Seek to start of next chunk, end is None if all chunks are read
+    fn next_chunk(&mut self) -> std::io::Result<()> {
+        self.end = match self.chunks.next() {
+            Some(chunk) => {
+                self.reader.seek(chunk.start())?;
+                Some(chunk.end())
+            }
+            None => None,
+        };
+
+        Ok(())
+    }
+
+    /// Read next record overlapping a region, return 0 if all chunks are read
+    fn read_record(&mut self, record: &mut noodles::bam::Record) -> std::io::Result<usize> {
+        loop {
+            match self.end {
+                None => return Ok(0),
+                Some(end) if self.reader.virtual_position() >= end => {
+                    self.next_chunk()?;
+                    continue;
+                }
+                Some(_) => (),
+            }
+
+            let nb_bytes_read = self.reader.read_record(record)?;
+            if nb_bytes_read == 0 {
+                self.end = None;
+            } else if region::overlaps(&self.intervals, record) {
+                return Ok(nb_bytes_read);
+            }
+        }
+    }
+}
+
+/// Bam input, all records, only records of regions or nothing after a read error
+enum Input {
+    Stream(Reader),
+    Regions(Box<Query>),
+    Empty,
+}
+
+impl Input {
+    /// Intervals of regions read, None if all records are read
+    fn intervals(&self) -> Option<&[region::Interval]> {
+        match self {
+            Input::Regions(query) => Some(&query.intervals),
+            _ => None,
+        }
+    }
+
+    fn read_record(&mut self, record: &mut noodles::bam::Record) -> std::io::Result<usize> {
+        match self {
+            Input::Stream(reader) => reader.read_record(record),
+            Input::Regions(query) => query.read_record(record),
+            Input::Empty => Ok(0),
+        }
+    }
+}
 
-/// Open a bam
+/// Open a bam, return reader and reference sequences dictionary
+///
+/// If regions isn't empty, index of bam is use to read only records of regions
 fn open(
-    path: &str,
+    source: stream::Source,
     buffer_size: usize,
-) -> anyhow::Result<noodles::bam::Reader<noodles::bgzf::Reader<std::io::BufReader<std::fs::File>>>>
-{
-    log::debug!("Open file {}", path);
-
-    let mut reader = noodles::bam::Reader::new(std::io::BufReader::with_capacity(
-        buffer_size,
-        std::fs::File::open(path)?,
-    ));
-    reader.read_header()?;
-    reader.read_reference_sequences()?;
-
-    Ok(reader)
+    regions: &[bed::Target],
+) -> anyhow::Result<(Input, dictionary::Dictionary)> {
+    log::debug!("Open file {}", source.path());
+
+    if regions.is_empty() {
+        let mut reader = noodles::bam::Reader::from(bgzf::ParallelReader::new(
+            std::io::BufReader::with_capacity(buffer_size, source.open()?),
+        ));
+        reader.read_header()?;
+        let reference_sequences = reader.read_reference_sequences()?;
+
+        Ok((
+            Input::Stream(reader),
+            dictionary::from_header(&reference_sequences),
+        ))
+    } else {
+        let path = source.path();
+        let mut reader = noodles::bam::Reader::new(std::fs::File::open(path)?);
+        reader.read_header()?;
+        let dictionary = dictionary::from_header(&reader.read_reference_sequences()?);
+
+        let intervals = region::resolve(regions, &dictionary);
+        let chunks = region::chunks(path, &intervals)?;
+
+        Ok((
+            Input::Regions(Box::new(Query::new(reader, chunks, intervals)?)),
+            dictionary,
+        ))
+    }
 }
 
 /// Struct to parse Bam file
 pub struct Bam {
     buffer_size: usize,
-    paths: Vec<String>,
+    sources: Vec<stream::Source>,
+    current_index: usize,
     local_record: noodles::bam::Record,
-    current_input: noodles::bam::Reader<noodles::bgzf::Reader<std::io::BufReader<std::fs::File>>>,
+    current_input: Input,
+    current_path: String,
+    regions: Vec<bed::Target>,
+    dictionaries: dictionary::Dictionaries,
+    depths: Option<coverage::Depths>,
+    targets: Option<std::sync::Arc<bed::Targets>>,
+    sweep: Option<coverage::Sweep>,
 }
 
 impl Bam {
     /// Create a Bam struct, with inputs path and size of read buffer
     /// If last file of inputs can't be open this function return an anyhow::Error
     /// Other inputs can't be open is ignored, an error message is put in log
-    pub fn new(mut inputs: Vec<String>, buffer_size: usize) -> anyhow::Result<Self> {
-        let first_path = inputs
+    ///
+    /// Each record is tagged with the index of its source path in inputs
+    pub fn new<S: Into<stream::Source>>(
+        inputs: Vec<S>,
+        buffer_size: usize,
+    ) -> anyhow::Result<Self> {
+        let mut inputs: Vec<stream::Source> = inputs.into_iter().map(Into::into).collect();
+        let first = inputs
             .pop()
             .ok_or_else(|| anyhow::anyhow!("inputs is empty"))?;
+        let first_path = first.path().to_string();
+
+        let (current_input, dictionary) =
+            open(first, buffer_size, &[]).with_context(|| error::Error::BamOpenError {
+                path: first_path.clone(),
+            })?;
+
+        let dictionaries = dictionary::Dictionaries::new();
+        dictionaries.insert(inputs.len(), dictionary);
 
         Ok(Self {
             buffer_size,
-            paths: inputs,
+            current_index: inputs.len(),
+            sources: inputs,
             local_record: noodles::bam::Record::default(),
-            current_input: open(&first_path, buffer_size).with_context(|| {
-                error::Error::BamOpenError {
-                    path: first_path.clone(),
-                }
-            })?,
+            current_input,
+            current_path: first_path,
+            regions: Vec::new(),
+            dictionaries,
+            depths: None,
+            targets: None,
+            sweep: None,
+        })
+    }
+
+    /// Read only records overlapping regions, each bam must have an index (.bai or .csi)
+    ///
+    /// Current file is reopen with its index, an error is return if it failled
+    pub fn with_regions(mut self, regions: Vec<bed::Target>) -> anyhow::Result<Self> {
+        self.regions = regions;
+
+        let (current_input, dictionary) = open(
+            self.current_path.as_str().into(),
+            self.buffer_size,
+            &self.regions,
+        )
+        .with_context(|| error::Error::BamOpenError {
+            path: self.current_path.clone(),
+        })?;
+        self.current_input = current_input;
+        self.dictionaries.insert(self.current_index, dictionary);
+        self.sweep = self.new_sweep();
+
+        Ok(self)
+    }
+
+    /// Compute depth of coverage of each file, files must be sorted by coordinate
+    pub fn with_coverage(mut self) -> Self {
+        self.depths = Some(coverage::Depths::new());
+        self.sweep = self.new_sweep();
+
+        self
+    }
+
+    /// Compute depth of coverage of targets too, coverage must be enable
+    pub fn with_targets(mut self, targets: std::sync::Arc<bed::Targets>) -> Self {
+        self.targets = Some(targets);
+        self.sweep = self.new_sweep();
+
+        self
+    }
+
+    /// Depth of coverage of each file, a file depth is set at end of file
+    /// None if coverage isn't enable
+    pub fn depths(&self) -> Option<coverage::Depths> {
+        self.depths.clone()
+    }
+
+    /// Reference sequences dictionary of each file, a file dictionary is set when file is open
+    pub fn dictionaries(&self) -> dictionary::Dictionaries {
+        self.dictionaries.clone()
+    }
+}
+
+impl Bam {
+    /// Create a coverage sweep for current file, if coverage is enable
+    fn new_sweep(&self) -> Option<coverage::Sweep> {
+        self.depths.as_ref()?;
+
+        let dictionary = self.dictionaries.get(self.current_index)?;
+        let sweep = coverage::Sweep::new(&self.current_path, &dictionary);
+
+        let sweep = match self.current_input.intervals() {
+            Some(intervals) => sweep.with_regions(intervals),
+            None => sweep,
+        };
+
+        Some(match self.targets.as_ref() {
+            Some(targets) => sweep.with_targets(targets.clone()),
+            None => sweep,
         })
     }
+
+    /// Store depth of current file
+    fn finish_sweep(&mut self) {
+        if let (Some(depths), Some(sweep)) = (self.depths.as_ref(), self.sweep.take()) {
+            if let Some(depth) = sweep.finish() {
+                depths.insert(self.current_index, depth);
+            }
+        }
+    }
+
+    /// Open next input, return None if all inputs are read
+    fn next_input(&mut self) -> Option<anyhow::Result<()>> {
+        self.finish_sweep();
+
+        let new_source = self.sources.pop()?;
+        let new_path = new_source.path().to_string();
+        self.current_index = self.sources.len();
+
+        match open(new_source, self.buffer_size, &self.regions) {
+            Ok((new_input, dictionary)) => {
+                self.current_input = new_input;
+                self.current_path = new_path;
+                self.dictionaries.insert(self.current_index, dictionary);
+                self.sweep = self.new_sweep();
+                Some(Ok(()))
+            }
+            Err(e) => Some(Err(e.context(error::Error::BamOpenError {
+                path: new_path.clone(),
+            }))),
+        }
+    }
 }
 
 impl Iterator for Bam {
-    type Item = anyhow::Result<noodles::bam::record::Record>;
+    type Item = (usize, anyhow::Result<noodles::bam::record::Record>);
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.current_input.read_record(&mut self.local_record) {
             Ok(nb_bytes_read) => {
                 if nb_bytes_read == 0 {
-                    if let Some(new_path) = self.paths.pop() {
-                        match open(&new_path, self.buffer_size) {
-                            Ok(new_input) => {
-                                self.current_input = new_input;
-                                self.next()
-                            }
-                            Err(e) => Some(Err(e.context(error::Error::BamOpenError {
-                                path: new_path.clone(),
-                            }))),
-                        }
-                    } else {
-                        None // No new file end of iterator
+                    match self.next_input()? {
+                        Ok(()) => self.next(),
+                        Err(e) => Some((self.current_index, Err(e))),
                     }
                 } else {
-                    Some(Ok(self.local_record.clone()))
+                    if let Some(sweep) = self.sweep.as_mut() {
+                        sweep.observe(&self.local_record);
+                    }
+                    Some((self.current_index, Ok(self.local_record.clone())))
                 }
             }
-            Err(e) => Some(Err(
-                anyhow::Error::new(e).context(error::Error::BamParsingError)
+            Err(e) => Some((
+                self.current_index,
+                Err(anyhow::Error::new(e).context(error::Error::BamParsingError)),
             )),
         }
     }
 }
 
-pub fn worker(wrapper: anyhow::Result<noodles::bam::record::Record>) -> (u64, u64, u64, u64) {
-    match wrapper {
-        Ok(seq) => {
-            let mut at: u64 = 0;
-            let mut gc: u64 = 0;
-            let mut other: u64 = 0;
-
-            for n in seq.sequence().bases() {
-                match n {
-                    noodles::bam::record::sequence::Base::A
-                    | noodles::bam::record::sequence::Base::T => at += 1,
-                    noodles::bam::record::sequence::Base::C
-                    | noodles::bam::record::sequence::Base::G => gc += 1,
-                    _ => other += 1,
+impl batch::Reader for Bam {
+    type Record = noodles::bam::Record;
+
+    fn fill(&mut self, batch: &mut batch::Batch<noodles::bam::Record>) -> bool {
+        batch.clear(self.current_index);
+
+        while !batch.is_full() {
+            match self.current_input.read_record(batch.next_slot()) {
+                Ok(0) => {
+                    batch.remove_slot();
+                    if !batch.is_empty() {
+                        return true;
+                    }
+
+                    match self.next_input() {
+                        Some(Ok(())) => batch.clear(self.current_index),
+                        Some(Err(e)) => {
+                            batch.clear(self.current_index);
+                            batch.push_error(e);
+                            return true;
+                        }
+                        None => return false,
+                    }
                 }
-            }
+                Ok(nb_bytes_read) => {
+                    if let (Some(sweep), Some(record)) =
+                        (self.sweep.as_mut(), batch.records().last())
+                    {
+                        sweep.observe(record);
+                    }
+                    batch.add_bytes(nb_bytes_read);
+                }
+                Err(e) => {
+                    batch.remove_slot();
+                    batch.push_error(anyhow::Error::new(e).context(error::Error::BamParsingError));
 
-            (at, gc, other, 1)
-        }
-        Err(e) => {
-            e.chain().for_each(|b| log::error!("{}", b));
-            (0, 0, 0, 0)
+                    // input can't be read after an error, rest of input is skip
+                    self.current_input = Input::Empty;
+                    return true;
+                }
+            }
         }
+
+        true
     }
 }
 
@@ -174,11 +431,11 @@ mod t {
     fn open_bam_file() {
         let (tmp_file, path) = create_bam_file();
 
-        assert!(open(&path, 10).is_ok());
+        assert!(open(path.as_str().into(), 10, &[]).is_ok());
 
         tmp_file.close().unwrap();
 
-        assert!(open(&path, 10).is_err());
+        assert!(open(path.as_str().into(), 10, &[]).is_err());
     }
 
     #[test]
@@ -199,15 +456,15 @@ mod t {
         let mut reader = Bam::new(vec![path], 10).unwrap();
 
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [18, 132]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [18, 132, 18, 132]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [17, 33, 36, 132, 20, 130, 36, 33, 33, 34, 68, 18, 64]
         );
     }
@@ -222,15 +479,15 @@ mod t {
         let mut reader = Bam::new(vec![path], 10).unwrap();
 
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [18, 132]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [18, 132, 18, 132]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [17, 33, 36, 132, 20, 130, 36, 33, 33, 34, 68, 18, 64]
         );
 
@@ -246,27 +503,27 @@ mod t {
 
         let mut reader = Bam::new(vec![path2, path1], 10).unwrap();
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [18, 132]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [18, 132, 18, 132]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [17, 33, 36, 132, 20, 130, 36, 33, 33, 34, 68, 18, 64]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [18, 132]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [18, 132, 18, 132]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [17, 33, 36, 132, 20, 130, 36, 33, 33, 34, 68, 18, 64]
         );
         assert!(reader.next().is_none());
@@ -282,51 +539,314 @@ mod t {
 
         let mut reader = Bam::new(vec![path3, path2, path1], 10).unwrap();
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [18, 132]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [18, 132, 18, 132]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [17, 33, 36, 132, 20, 130, 36, 33, 33, 34, 68, 18, 64]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [18, 132]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [18, 132, 18, 132]
         );
         assert_eq!(
-            reader.next().unwrap().unwrap().sequence().as_ref()[..],
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
             [17, 33, 36, 132, 20, 130, 36, 33, 33, 34, 68, 18, 64]
         );
 
         let failled_record = reader.next();
         assert!(failled_record.is_some());
-        assert!(failled_record.unwrap().is_err());
+
+        let (index, record) = failled_record.unwrap();
+        assert_eq!(index, 0);
+        assert!(record.is_err());
     }
 
     #[test]
-    fn run_worker() {
-        let r1 = noodles::bam::Record::try_from_sam_record(
-            noodles::sam::Header::builder()
-                .build()
-                .reference_sequences(),
-            &noodles::sam::Record::builder()
-                .set_read_name("1".parse().unwrap())
-                .set_flags(noodles::sam::record::Flags::UNMAPPED)
-                .set_sequence("ACTGN".parse().unwrap())
-                .build()
-                .unwrap(),
-        )
-        .unwrap();
-        assert_eq!(worker(Ok(r1)), (2, 2, 1, 1));
+    fn iterate_over_two_bam_index() {
+        let (_file1, path1) = create_bam_file();
+        let (_file2, path2) = create_bam_file();
+
+        let reader = Bam::new(vec![path2, path1], 10).unwrap();
+        let indexes: Vec<usize> = reader.map(|(index, _)| index).collect();
+
+        assert_eq!(indexes, vec![1, 1, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn dictionaries() {
+        let (_file1, path1) = create_bam_file();
+        let (_file2, path2) = create_bam_file();
+
+        let reader = Bam::new(vec![path2, path1], 10).unwrap();
+        let dictionaries = reader.dictionaries();
+
+        assert_eq!(dictionaries.get(1).unwrap().len(), 0);
+        assert!(dictionaries.get(0).is_none());
+
+        assert_eq!(reader.count(), 6);
+        assert!(dictionaries.get(0).is_some());
+    }
+
+    #[test]
+    fn coverage() {
+        let tmp_file = tempfile::NamedTempFile::new().unwrap();
+
+        {
+            let mut bam_writer = noodles::bam::Writer::new(tmp_file.reopen().unwrap());
+
+            let header = noodles::sam::Header::builder()
+                .add_reference_sequence(
+                    noodles::sam::header::ReferenceSequence::new("chr1".parse().unwrap(), 10)
+                        .unwrap(),
+                )
+                .build();
+            bam_writer.write_header(&header).unwrap();
+            bam_writer
+                .write_reference_sequences(header.reference_sequences())
+                .unwrap();
+
+            for (name, position) in [("1", 1), ("2", 3)] {
+                let record = noodles::sam::Record::builder()
+                    .set_read_name(name.parse().unwrap())
+                    .set_flags(noodles::sam::record::Flags::empty())
+                    .set_reference_sequence_name("chr1".parse().unwrap())
+                    .set_position(position.try_into().unwrap())
+                    .set_cigar("4M".parse().unwrap())
+                    .set_sequence("ACTG".parse().unwrap())
+                    .build()
+                    .unwrap();
+                bam_writer
+                    .write_sam_record(header.reference_sequences(), &record)
+                    .unwrap();
+            }
+        }
+
+        let path = tmp_file.path().to_str().unwrap().to_string();
+
+        let reader = Bam::new(vec![path.clone()], 10).unwrap();
+        assert!(reader.depths().is_none());
+
+        let mut reader = Bam::new(vec![path], 10).unwrap().with_coverage();
+        let depths = reader.depths().unwrap();
+        let mut batch = batch::Batch::default();
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert!(depths.get(0).is_none());
+        assert!(!batch::Reader::fill(&mut reader, &mut batch));
+
+        let depth = depths.get(0).unwrap();
+        assert_eq!(depth.references[0].positions, 10);
+        assert_eq!(depth.references[0].sum, 8);
+        assert_eq!(depth.histogram[..3], [4, 4, 2]);
+    }
+
+    #[test]
+    fn regions() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("reads.bam");
+        let path = path.to_str().unwrap().to_string();
+
+        {
+            let mut bam_writer = noodles::bam::Writer::new(std::fs::File::create(&path).unwrap());
+
+            let header = noodles::sam::Header::builder()
+                .add_reference_sequence(
+                    noodles::sam::header::ReferenceSequence::new("chr1".parse().unwrap(), 1000)
+                        .unwrap(),
+                )
+                .add_reference_sequence(
+                    noodles::sam::header::ReferenceSequence::new("chr2".parse().unwrap(), 1000)
+                        .unwrap(),
+                )
+                .build();
+            bam_writer.write_header(&header).unwrap();
+            bam_writer
+                .write_reference_sequences(header.reference_sequences())
+                .unwrap();
+
+            for (name, chrom, position) in [
+                ("1", "chr1", 10),
+                ("2", "chr1", 500),
+                ("3", "chr2", 10),
+                ("4", "chr2", 200),
+            ] {
+                let record = noodles::sam::Record::builder()
+                    .set_read_name(name.parse().unwrap())
+                    .set_flags(noodles::sam::record::Flags::empty())
+                    .set_reference_sequence_name(chrom.parse().unwrap())
+                    .set_position(position.try_into().unwrap())
+                    .set_cigar("4M".parse().unwrap())
+                    .set_sequence("ACTG".parse().unwrap())
+                    .build()
+                    .unwrap();
+                bam_writer
+                    .write_sam_record(header.reference_sequences(), &record)
+                    .unwrap();
+            }
+        }
+
+        let mut reader = noodles::bam::Reader::new(std::fs::File::open(&path).unwrap());
+        reader.read_header().unwrap();
+        reader.read_reference_sequences().unwrap();
+
+        let mut builder = noodles::bam::bai::Index::builder();
+        let mut record = noodles::bam::Record::default();
+        let mut start = reader.virtual_position();
+        while reader.read_record(&mut record).unwrap() != 0 {
+            let end = reader.virtual_position();
+            builder
+                .add_record(&record, region::Chunk::new(start, end))
+                .unwrap();
+            start = end;
+        }
+        noodles::bam::bai::write(format!("{}.bai", path), &builder.build(2)).unwrap();
+
+        let names = |regions: &[&str]| -> Vec<String> {
+            Bam::new(vec![path.clone()], 10)
+                .unwrap()
+                .with_regions(regions.iter().map(|r| region::parse(r).unwrap()).collect())
+                .unwrap()
+                .map(|(_, record)| {
+                    record
+                        .unwrap()
+                        .read_name()
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .to_string()
+                })
+                .collect()
+        };
+
+        assert_eq!(names(&["chr1:1-100"]), vec!["1"]);
+        assert_eq!(names(&["chr1:13-13", "chr2"]), vec!["1", "3", "4"]);
+        assert_eq!(names(&["chr1:1-600", "chr1:400-600"]), vec!["1", "2"]);
+        assert_eq!(names(&["chr2:14-199"]), Vec::<String>::new());
+        assert_eq!(names(&["chrM"]), Vec::<String>::new());
+
+        let target = |chrom: &str, start: u64, end: u64, name: &str| bed::Target {
+            chrom: chrom.to_string(),
+            start,
+            end,
+            name: name.to_string(),
+        };
+        let targets = bed::Targets::new(vec![
+            target("chr1", 0, 100, "a"),
+            target("chr2", 190, 230, "b"),
+            target("chr2", 240, 300, "c"),
+        ]);
+
+        let mut reader = Bam::new(vec![path.clone()], 10)
+            .unwrap()
+            .with_regions(vec![region::parse("chr2:150-250").unwrap()])
+            .unwrap()
+            .with_coverage()
+            .with_targets(std::sync::Arc::new(targets));
+        let depths = reader.depths().unwrap();
+        let mut batch = batch::Batch::default();
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.records().len(), 1);
+        assert!(!batch::Reader::fill(&mut reader, &mut batch));
+        let depth = depths.get(0).unwrap();
+        assert_eq!(depth.references.len(), 1);
+        assert_eq!(depth.references[0].name, "chr2");
+        assert_eq!(depth.references[0].positions, 101);
+        assert_eq!(depth.references[0].sum, 4);
+
+        // Target outside of regions isn't read, only positions of targets in regions are count
+        let targets: Vec<(&str, u64, u64)> = depth
+            .targets
+            .iter()
+            .map(|t| (t.name.as_str(), t.positions, t.sum))
+            .collect();
+        assert_eq!(targets, vec![("a", 0, 0), ("b", 40, 4), ("c", 10, 0)]);
+        assert_eq!(depth.target_sum, 4);
+        assert_eq!(depth.target_histogram.iter().sum::<u64>(), 50);
+
+        std::fs::remove_file(format!("{}.bai", path)).unwrap();
+        assert!(Bam::new(vec![path], 10)
+            .unwrap()
+            .with_regions(vec![region::parse("chr1").unwrap()])
+            .is_err());
+    }
+
+    #[test]
+    fn fill_batch() {
+        let (_file1, path1) = create_bam_file();
+        let (_file2, path2) = create_bam_file();
+        let (file3, path3) = create_bam_file();
+
+        file3.close().unwrap();
 
-        assert_eq!(worker(Err(anyhow::anyhow!("prout"))), (0, 0, 0, 0));
+        let mut reader = Bam::new(vec![path3, path2, path1], 10).unwrap();
+        let mut batch = batch::Batch::default();
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.index(), 2);
+        assert_eq!(batch.records().len(), 3);
+        assert_eq!(
+            batch.records()[1].sequence().as_ref()[..],
+            [18, 132, 18, 132]
+        );
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.index(), 1);
+        assert_eq!(batch.records().len(), 3);
+        assert_eq!(batch.records()[0].sequence().as_ref()[..], [18, 132]);
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.index(), 0);
+        assert!(batch.records().is_empty());
+        assert_eq!(batch.take_errors().len(), 1);
+
+        assert!(!batch::Reader::fill(&mut reader, &mut batch));
+    }
+
+    struct Failing;
+
+    impl std::io::Read for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "read failled",
+            ))
+        }
+    }
+
+    #[test]
+    fn fill_batch_read_error() {
+        let (_file1, path1) = create_bam_file();
+        let (_file2, path2) = create_bam_file();
+
+        let mut reader = Bam::new(vec![path2, path1], 10).unwrap();
+        let failing: stream::Stream = Box::new(Failing);
+        reader.current_input = Input::Stream(noodles::bam::Reader::from(
+            bgzf::ParallelReader::new(std::io::BufReader::new(failing)),
+        ));
+        let mut batch = batch::Batch::default();
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.index(), 1);
+        assert!(batch.records().is_empty());
+        assert_eq!(batch.take_errors().len(), 1);
+
+        // rest of failling input is skip, next input is read
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.index(), 0);
+        assert_eq!(batch.records().len(), 3);
+        assert!(batch.take_errors().is_empty());
+
+        assert!(!batch::Reader::fill(&mut reader, &mut batch));
     }
 }
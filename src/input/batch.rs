@@ -0,0 +1,231 @@
+//! Batch of records, read by one thread and process by rayon thread
+//!
+//! Records of a batch are reused between batch, reader overwrite them in place to avoid an
+//! allocation by record.
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+
+/// Default number of bytes of records in a batch
+pub const BATCH_BYTES: usize = 8 << 20;
+
+/// Number of records in a batch build by [Batched]
+pub const BATCHED_RECORDS: usize = 4096;
+
+/// A chunk of records from same file
+pub struct Batch<R> {
+    index: usize,
+    records: Vec<R>,
+    len: usize,
+    nb_bytes: usize,
+    capacity: usize,
+    errors: Vec<anyhow::Error>,
+}
+
+impl<R> Batch<R>
+where
+    R: Default,
+{
+    /// Create an empty batch, a batch is full when it contains more than capacity bytes
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            index: 0,
+            records: Vec::new(),
+            len: 0,
+            nb_bytes: 0,
+            capacity,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Index of file of records
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Records of batch
+    pub fn records(&self) -> &[R] {
+        &self.records[..self.len]
+    }
+
+    /// Take errors append durring batch filling
+    pub fn take_errors(&mut self) -> Vec<anyhow::Error> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Return true if batch contains no record and no error
+    pub fn is_empty(&self) -> bool {
+        self.len == 0 && self.errors.is_empty()
+    }
+
+    /// Return true if batch can't accept more record
+    pub fn is_full(&self) -> bool {
+        self.nb_bytes >= self.capacity
+    }
+
+    /// Remove all records and errors, records memory is keep
+    pub fn clear(&mut self, index: usize) {
+        self.index = index;
+        self.len = 0;
+        self.nb_bytes = 0;
+        self.errors.clear();
+    }
+
+    /// Get a record to overwrite, it's add in batch
+    pub fn next_slot(&mut self) -> &mut R {
+        if self.len == self.records.len() {
+            self.records.push(R::default());
+        }
+        self.len += 1;
+
+        &mut self.records[self.len - 1]
+    }
+
+    /// Remove last record get by [Batch::next_slot], use when read of record failled
+    pub fn remove_slot(&mut self) {
+        self.len = self.len.saturating_sub(1);
+    }
+
+    /// Count bytes read for last record
+    pub fn add_bytes(&mut self, nb_bytes: usize) {
+        self.nb_bytes += nb_bytes;
+    }
+
+    /// Add an error
+    pub fn push_error(&mut self, error: anyhow::Error) {
+        self.errors.push(error);
+    }
+}
+
+impl<R> Default for Batch<R>
+where
+    R: Default,
+{
+    fn default() -> Self {
+        Self::new(BATCH_BYTES)
+    }
+}
+
+/// Trait of input that can fill a batch
+pub trait Reader: Send {
+    /// Type of record read
+    type Record: Default + Send + Sync;
+
+    /// Clear batch and fill it with records of same file, return false if input is end
+    fn fill(&mut self, batch: &mut Batch<Self::Record>) -> bool;
+}
+
+/// Build batch from an iterator of records tagged by file index, batch contains at most
+/// [BATCHED_RECORDS] records
+pub struct Batched<I>
+where
+    I: Iterator,
+{
+    inner: std::iter::Peekable<I>,
+}
+
+impl<I> Batched<I>
+where
+    I: Iterator,
+{
+    /// Create a Batched from an iterator
+    pub fn new(inner: I) -> Self {
+        Self {
+            inner: inner.peekable(),
+        }
+    }
+}
+
+impl<R, I> Reader for Batched<I>
+where
+    R: Default + Send + Sync,
+    I: Iterator<Item = (usize, anyhow::Result<R>)> + Send,
+{
+    type Record = R;
+
+    fn fill(&mut self, batch: &mut Batch<R>) -> bool {
+        match self.inner.peek() {
+            Some((index, _)) => batch.clear(*index),
+            None => return false,
+        }
+
+        let mut nb_records = 0;
+        while nb_records < BATCHED_RECORDS {
+            match self.inner.peek() {
+                Some((index, _)) if *index == batch.index() => (),
+                _ => break,
+            }
+
+            match self.inner.next() {
+                Some((_, Ok(record))) => *batch.next_slot() = record,
+                Some((_, Err(e))) => batch.push_error(e),
+                None => break,
+            }
+            nb_records += 1;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn batch() {
+        let mut batch: Batch<Vec<u8>> = Batch::new(8);
+
+        assert!(batch.is_empty());
+
+        batch.next_slot().extend_from_slice(b"ACGT");
+        batch.add_bytes(4);
+        assert!(!batch.is_full());
+
+        batch.next_slot().extend_from_slice(b"TTTT");
+        batch.add_bytes(4);
+        assert!(batch.is_full());
+
+        batch.next_slot();
+        batch.remove_slot();
+        batch.push_error(anyhow::anyhow!("error"));
+
+        assert_eq!(batch.records(), &[b"ACGT".to_vec(), b"TTTT".to_vec()]);
+        assert_eq!(batch.take_errors().len(), 1);
+
+        batch.clear(2);
+        assert!(batch.is_empty());
+        assert!(!batch.is_full());
+        assert_eq!(batch.index(), 2);
+
+        // memory of record is keep
+        assert_eq!(batch.next_slot(), b"ACGT");
+    }
+
+    #[test]
+    fn batched() {
+        let mut records: Vec<(usize, anyhow::Result<u64>)> =
+            vec![(1, Ok(1)), (1, Err(anyhow::anyhow!("error"))), (0, Ok(2))];
+        records.extend((0..BATCHED_RECORDS + 1).map(|i| (0, Ok(i as u64))));
+
+        let mut reader = Batched::new(records.into_iter());
+        let mut batch = Batch::default();
+
+        assert!(reader.fill(&mut batch));
+        assert_eq!(batch.index(), 1);
+        assert_eq!(batch.records(), &[1]);
+        assert_eq!(batch.take_errors().len(), 1);
+
+        assert!(reader.fill(&mut batch));
+        assert_eq!(batch.index(), 0);
+        assert_eq!(batch.records().len(), BATCHED_RECORDS);
+
+        assert!(reader.fill(&mut batch));
+        assert_eq!(batch.records().len(), 2);
+
+        assert!(!reader.fill(&mut batch));
+    }
+}
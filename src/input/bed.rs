@@ -0,0 +1,295 @@
+//! Parse bed file of target regions and index them
+
+/* std use */
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/* crates use */
+use anyhow::Context;
+
+/* project use */
+use crate::error;
+use crate::input::bgzf;
+use crate::input::stream;
+
+/// A target region, coordinates are 0-based and end is exclude like in bed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    /// Name of reference sequence
+    pub chrom: String,
+
+    /// Start of region
+    pub start: u64,
+
+    /// End of region
+    pub end: u64,
+
+    /// Name of region, fourth column of bed or chrom:start-end (1-based) if absent
+    pub name: String,
+}
+
+/// Targets of one reference sequence, sorted by start
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChromTargets {
+    /// Index of first target of this reference sequence in [Targets::targets]
+    pub offset: usize,
+
+    /// Start and end of targets
+    pub intervals: Vec<(u64, u64)>,
+
+    /// Maximal end of targets before each index, use to find overlaps of nested targets
+    pub max_ends: Vec<u64>,
+
+    /// Union of targets, sorted and not overlapping
+    pub merged: Vec<(u64, u64)>,
+}
+
+impl ChromTargets {
+    fn new(offset: usize, intervals: Vec<(u64, u64)>) -> Self {
+        let max_ends = intervals
+            .iter()
+            .scan(0, |max, (_, end)| {
+                *max = (*max).max(*end);
+                Some(*max)
+            })
+            .collect();
+
+        let mut merged: Vec<(u64, u64)> = Vec::new();
+        for (start, end) in intervals.iter() {
+            match merged.last_mut() {
+                Some(last) if *start <= last.1 => last.1 = last.1.max(*end),
+                _ => merged.push((*start, *end)),
+            }
+        }
+
+        Self {
+            offset,
+            intervals,
+            max_ends,
+            merged,
+        }
+    }
+
+    /// Return true if region start..end overlap a target
+    pub fn overlaps(&self, start: u64, end: u64) -> bool {
+        let before = self.intervals.partition_point(|(s, _)| *s < end);
+
+        before > 0 && self.max_ends[before - 1] > start
+    }
+
+    /// Number of bases between region start..end and nearest target, 0 if they overlap
+    pub fn distance(&self, start: u64, end: u64) -> u64 {
+        let before = self.intervals.partition_point(|(s, _)| *s < end);
+
+        let left = if before > 0 {
+            start.saturating_sub(self.max_ends[before - 1])
+        } else {
+            u64::MAX
+        };
+
+        let right = self
+            .intervals
+            .get(before)
+            .map_or(u64::MAX, |(s, _)| s - end);
+
+        left.min(right)
+    }
+}
+
+/// Target regions index by reference sequence
+#[derive(Debug, Clone, PartialEq)]
+pub struct Targets {
+    targets: Vec<Target>,
+    chroms: Vec<ChromTargets>,
+    index: HashMap<String, usize>,
+}
+
+impl Targets {
+    /// Build index of targets
+    pub fn new(mut targets: Vec<Target>) -> Self {
+        let mut order: HashMap<String, usize> = HashMap::new();
+        for target in targets.iter() {
+            let len = order.len();
+            order.entry(target.chrom.clone()).or_insert(len);
+        }
+        targets.sort_by_key(|t| (order[&t.chrom], t.start, t.end));
+
+        let mut chroms = Vec::new();
+        let mut index = HashMap::new();
+        let mut offset = 0;
+        while offset < targets.len() {
+            let chrom = &targets[offset].chrom;
+            let len = targets[offset..]
+                .iter()
+                .take_while(|t| &t.chrom == chrom)
+                .count();
+
+            index.insert(chrom.clone(), chroms.len());
+            chroms.push(ChromTargets::new(
+                offset,
+                targets[offset..offset + len]
+                    .iter()
+                    .map(|t| (t.start, t.end))
+                    .collect(),
+            ));
+
+            offset += len;
+        }
+
+        Self {
+            targets,
+            chroms,
+            index,
+        }
+    }
+
+    /// All targets, sorted by reference sequence and start
+    pub fn targets(&self) -> &[Target] {
+        &self.targets
+    }
+
+    /// Targets of all reference sequences
+    pub fn chroms(&self) -> &[ChromTargets] {
+        &self.chroms
+    }
+
+    /// Index in [Targets::chroms] of targets of reference sequence name
+    pub fn chrom_index(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+}
+
+/// Parse a bed line, None if line is empty or a comment
+fn parse_line(line: &str) -> Option<anyhow::Result<Target>> {
+    if line.trim().is_empty()
+        || line.starts_with('#')
+        || line.starts_with("track")
+        || line.starts_with("browser")
+    {
+        return None;
+    }
+
+    let fields: Vec<&str> = line.trim_end().split('\t').collect();
+    if fields.len() < 3 {
+        return Some(Err(anyhow::anyhow!("bed line need at least 3 columns")));
+    }
+
+    let parse = || -> anyhow::Result<Target> {
+        let start: u64 = fields[1].parse()?;
+        let end: u64 = fields[2].parse()?;
+        if end < start {
+            anyhow::bail!("end of region is before start");
+        }
+
+        Ok(Target {
+            chrom: fields[0].to_string(),
+            start,
+            end,
+            name: fields
+                .get(3)
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| format!("{}:{}-{}", fields[0], start + 1, end)),
+        })
+    };
+
+    Some(parse())
+}
+
+/// Read all targets of a bed file
+pub fn read_targets(path: &str) -> anyhow::Result<Targets> {
+    log::debug!("Read targets {}", path);
+
+    let reader = std::io::BufReader::new(bgzf::get_reader(stream::open(path)?)?);
+
+    let mut targets = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        if let Some(target) = parse_line(&line?) {
+            targets.push(target.context(error::Error::BedParsingError { line: i + 1 })?);
+        }
+    }
+
+    Ok(Targets::new(targets))
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use std::io::Write;
+
+    fn target(chrom: &str, start: u64, end: u64) -> Target {
+        Target {
+            chrom: chrom.to_string(),
+            start,
+            end,
+            name: format!("{}:{}-{}", chrom, start + 1, end),
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert!(parse_line("").is_none());
+        assert!(parse_line("# comment").is_none());
+        assert!(parse_line("track name=exome").is_none());
+
+        assert_eq!(
+            parse_line("chr1\t10\t20\n").unwrap().unwrap(),
+            target("chr1", 10, 20)
+        );
+        assert_eq!(
+            parse_line("chr1\t10\t20\tBRCA1").unwrap().unwrap().name,
+            "BRCA1"
+        );
+
+        assert!(parse_line("chr1\t10").unwrap().is_err());
+        assert!(parse_line("chr1\tten\t20").unwrap().is_err());
+        assert!(parse_line("chr1\t20\t10").unwrap().is_err());
+    }
+
+    #[test]
+    fn index() {
+        let targets = Targets::new(vec![
+            target("chr2", 5, 10),
+            target("chr1", 30, 40),
+            target("chr1", 10, 50),
+            target("chr1", 60, 70),
+        ]);
+
+        assert_eq!(targets.targets()[0], target("chr2", 5, 10));
+        assert_eq!(targets.targets()[1], target("chr1", 10, 50));
+        assert_eq!(targets.chrom_index("chr1"), Some(1));
+        assert_eq!(targets.chrom_index("chrM"), None);
+
+        let chr1 = &targets.chroms()[1];
+        assert_eq!(chr1.offset, 1);
+        assert_eq!(chr1.intervals, vec![(10, 50), (30, 40), (60, 70)]);
+        assert_eq!(chr1.max_ends, vec![50, 50, 70]);
+        assert_eq!(chr1.merged, vec![(10, 50), (60, 70)]);
+
+        assert!(!chr1.overlaps(0, 10));
+        assert!(chr1.overlaps(0, 11));
+        assert!(chr1.overlaps(45, 47));
+        assert!(!chr1.overlaps(50, 55));
+        assert!(chr1.overlaps(55, 65));
+
+        assert_eq!(chr1.distance(0, 5), 5);
+        assert_eq!(chr1.distance(0, 11), 0);
+        assert_eq!(chr1.distance(45, 47), 0);
+        assert_eq!(chr1.distance(52, 55), 2);
+        assert_eq!(chr1.distance(56, 58), 2);
+        assert_eq!(chr1.distance(75, 80), 5);
+    }
+
+    #[test]
+    fn read_targets_() {
+        let mut tmp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(tmp_file, "track name=panel\nchr1\t10\t20\tA\nchr1\t0\t5\tB").unwrap();
+
+        let targets = read_targets(tmp_file.path().to_str().unwrap()).unwrap();
+        assert_eq!(targets.targets().len(), 2);
+        assert_eq!(targets.targets()[0].name, "B");
+
+        writeln!(tmp_file, "chr1\t10").unwrap();
+        assert!(read_targets(tmp_file.path().to_str().unwrap()).is_err());
+    }
+}
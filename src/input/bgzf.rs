@@ -0,0 +1,292 @@
+//! Parallel decompression of bgzf file
+//!
+//! A bgzf file is a serie of independent gzip block, a group of block is read and blocks are
+//! decompressed in parallel by rayon global thread pool.
+
+/* std use */
+use std::io::Read;
+
+/* crates use */
+use rayon::prelude::*;
+
+/* project use */
+use crate::input::stream;
+
+/// Size of gzip header before extra field
+const HEADER_LEN: usize = 12;
+
+/// Size of gzip header with bgzf extra field
+const BGZF_HEADER_LEN: usize = 18;
+
+/// Size of gzip footer (crc32 and uncompressed size)
+const FOOTER_LEN: usize = 8;
+
+/// Number of block decompressed by each thread in a group
+const BLOCKS_BY_THREAD: usize = 8;
+
+/// Return true if prefix is begin of a bgzf file
+pub fn is_bgzf(prefix: &[u8]) -> bool {
+    prefix.len() >= 16 && prefix[..4] == [0x1f, 0x8b, 0x08, 0x04] && prefix[12..14] == *b"BC"
+}
+
+/// Get a reader on decompressed content of stream, bgzf is decompressed in parallel other
+/// compression format is managed by niffler
+pub fn get_reader(mut stream: stream::Stream) -> anyhow::Result<stream::Stream> {
+    let mut prefix = Vec::with_capacity(BGZF_HEADER_LEN);
+    stream
+        .by_ref()
+        .take(BGZF_HEADER_LEN as u64)
+        .read_to_end(&mut prefix)?;
+
+    let stream: stream::Stream = Box::new(std::io::Cursor::new(prefix.clone()).chain(stream));
+
+    if is_bgzf(&prefix) {
+        log::debug!("Use parallel bgzf decompression");
+        Ok(Box::new(ParallelReader::new(stream)))
+    } else {
+        Ok(niffler::send::get_reader(stream)?.0)
+    }
+}
+
+/// Reader that decompress bgzf block in parallel
+pub struct ParallelReader<R> {
+    inner: R,
+    raw_blocks: Vec<Vec<u8>>,
+    buffer: Vec<u8>,
+    position: usize,
+    error: Option<std::io::Error>,
+}
+
+impl<R> ParallelReader<R>
+where
+    R: Read,
+{
+    /// Create a ParallelReader, blocks are decompressed by rayon thread pool
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            raw_blocks: Vec::new(),
+            buffer: Vec::new(),
+            position: 0,
+            error: None,
+        }
+    }
+
+    /// Read and decompress next group of blocks, return number of blocks read, 0 at end of file
+    ///
+    /// If reading of a block failled, previous blocks are decompressed and error is return by
+    /// next call
+    fn fill(&mut self) -> std::io::Result<usize> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+
+        let nb_blocks = rayon::current_num_threads() * BLOCKS_BY_THREAD;
+        self.raw_blocks.resize_with(nb_blocks, Vec::new);
+
+        let mut nb_read = 0;
+        while nb_read < nb_blocks {
+            match read_block(&mut self.inner, &mut self.raw_blocks[nb_read]) {
+                Ok(true) => nb_read += 1,
+                Ok(false) => break,
+                Err(e) if nb_read == 0 => return Err(e),
+                Err(e) => {
+                    self.error = Some(e);
+                    break;
+                }
+            }
+        }
+
+        let raw_blocks = &self.raw_blocks[..nb_read];
+        let blocks = raw_blocks
+            .par_iter()
+            .map(|raw| inflate(raw))
+            .collect::<std::io::Result<Vec<Vec<u8>>>>()?;
+
+        self.buffer.clear();
+        self.position = 0;
+        blocks.iter().for_each(|b| self.buffer.extend_from_slice(b));
+
+        Ok(nb_read)
+    }
+}
+
+impl<R> Read for ParallelReader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // empty bgzf block (end of file marker) can be in middle of file
+        while self.position == self.buffer.len() {
+            if self.fill()? == 0 {
+                return Ok(0);
+            }
+        }
+
+        let nb_bytes = std::cmp::min(buf.len(), self.buffer.len() - self.position);
+        buf[..nb_bytes].copy_from_slice(&self.buffer[self.position..self.position + nb_bytes]);
+        self.position += nb_bytes;
+
+        Ok(nb_bytes)
+    }
+}
+
+fn invalid_data(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Read a raw bgzf block in block, return false at end of file
+///
+/// Like noodles, an incomplete header at end of file is consider as end of file
+fn read_block<R>(reader: &mut R, block: &mut Vec<u8>) -> std::io::Result<bool>
+where
+    R: Read,
+{
+    block.clear();
+    reader
+        .by_ref()
+        .take(BGZF_HEADER_LEN as u64)
+        .read_to_end(block)?;
+
+    if block.len() < BGZF_HEADER_LEN {
+        return Ok(false);
+    } else if block[..4] != [0x1f, 0x8b, 0x08, 0x04] {
+        return Err(invalid_data("invalid bgzf block header"));
+    }
+
+    let extra_len = u16::from_le_bytes([block[10], block[11]]) as usize;
+    if HEADER_LEN + extra_len > BGZF_HEADER_LEN {
+        reader
+            .by_ref()
+            .take((HEADER_LEN + extra_len - BGZF_HEADER_LEN) as u64)
+            .read_to_end(block)?;
+    }
+
+    let mut block_size = None;
+    let mut extra = &block[HEADER_LEN..std::cmp::min(block.len(), HEADER_LEN + extra_len)];
+    while extra.len() >= 4 {
+        let field_len = u16::from_le_bytes([extra[2], extra[3]]) as usize;
+        if extra[..2] == *b"BC" && field_len == 2 && extra.len() >= 6 {
+            block_size = Some(u16::from_le_bytes([extra[4], extra[5]]) as usize + 1);
+        }
+        extra = &extra[std::cmp::min(extra.len(), 4 + field_len)..];
+    }
+
+    let block_size = block_size.ok_or_else(|| invalid_data("missing bgzf block size"))?;
+    if block_size < HEADER_LEN + extra_len + FOOTER_LEN {
+        return Err(invalid_data("invalid bgzf block size"));
+    }
+
+    let remain = block_size - block.len();
+    reader.by_ref().take(remain as u64).read_to_end(block)?;
+    if block.len() != block_size {
+        return Err(invalid_data("truncated bgzf block"));
+    }
+
+    Ok(true)
+}
+
+/// Decompress a raw bgzf block and check its crc32
+fn inflate(block: &[u8]) -> std::io::Result<Vec<u8>> {
+    let extra_len = u16::from_le_bytes([block[10], block[11]]) as usize;
+    let cdata = &block[HEADER_LEN + extra_len..block.len() - FOOTER_LEN];
+    let footer = &block[block.len() - FOOTER_LEN..];
+
+    let crc32 = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+    let size = u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]) as usize;
+
+    let mut data = Vec::with_capacity(size);
+    flate2::read::DeflateDecoder::new(cdata).read_to_end(&mut data)?;
+
+    let mut crc = flate2::Crc::new();
+    crc.update(&data);
+    if data.len() != size || crc.sum() != crc32 {
+        return Err(invalid_data("bgzf block checksum mismatch"));
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use std::io::Write;
+
+    fn content() -> Vec<u8> {
+        (0..200_000).map(|i| b"ACGT\n"[i % 5]).collect()
+    }
+
+    fn bgzf_content() -> Vec<u8> {
+        let mut writer = noodles::bgzf::Writer::new(Vec::new());
+        writer.write_all(&content()).unwrap();
+
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn is_bgzf_() {
+        assert!(is_bgzf(&bgzf_content()));
+
+        let mut gzip = Vec::new();
+        {
+            let mut writer = niffler::get_writer(
+                Box::new(&mut gzip),
+                niffler::compression::Format::Gzip,
+                niffler::compression::Level::One,
+            )
+            .unwrap();
+            writer.write_all(&content()).unwrap();
+        }
+        assert!(!is_bgzf(&gzip));
+        assert!(!is_bgzf(b"@1\nACGT\n+\nIIII\n"));
+    }
+
+    #[test]
+    fn decompress() {
+        let compressed = bgzf_content();
+
+        let mut data = Vec::new();
+        ParallelReader::new(&compressed[..])
+            .read_to_end(&mut data)
+            .unwrap();
+
+        assert_eq!(data, content());
+    }
+
+    #[test]
+    fn decompress_by_get_reader() {
+        let mut data = Vec::new();
+        get_reader(Box::new(std::io::Cursor::new(bgzf_content())))
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+
+        assert_eq!(data, content());
+
+        data.clear();
+        get_reader(Box::new(std::io::Cursor::new(content())))
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+
+        assert_eq!(data, content());
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut compressed = bgzf_content();
+        let middle = compressed.len() / 2;
+        compressed[middle] ^= 0xFF;
+
+        let mut data = Vec::new();
+        assert!(ParallelReader::new(&compressed[..])
+            .read_to_end(&mut data)
+            .is_err());
+
+        let mut data = Vec::new();
+        assert!(ParallelReader::new(&bgzf_content()[..100])
+            .read_to_end(&mut data)
+            .is_err());
+    }
+}
@@ -0,0 +1,790 @@
+//! Depth of coverage of coordinate-sorted alignment files
+//!
+//! Depth is compute by a sweep on records in file order, only depth of positions not yet
+//! closed by start of last record is keep in memory, so memory usage depends on length of
+//! alignments not on length of reference sequences.
+
+/* std use */
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/* crates use */
+use noodles::sam::record::cigar::op::Kind;
+
+/* project use */
+use crate::input::bed;
+use crate::input::dictionary;
+use crate::input::region;
+
+/// Last bin of depth histogram, it contains all positions with a greater depth
+pub const MAX_DEPTH: usize = 1000;
+
+/// Breadth of coverage thresholds
+pub const THRESHOLDS: [u64; 3] = [1, 10, 30];
+
+/// Depth summary of one reference sequence
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ReferenceDepth {
+    /// Name of reference sequence
+    pub name: String,
+
+    /// Number of positions
+    pub positions: u64,
+
+    /// Sum of depth of all positions
+    pub sum: u64,
+
+    /// Number of positions with a depth greater or equal to each [THRESHOLDS]
+    pub covered: [u64; 3],
+}
+
+impl ReferenceDepth {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Default::default()
+        }
+    }
+
+    /// Add n positions with depth
+    fn add(&mut self, depth: u64, n: u64) {
+        self.positions += n;
+        self.cover(depth, n);
+    }
+
+    /// Add depth of n positions already count
+    fn cover(&mut self, depth: u64, n: u64) {
+        self.sum += depth * n;
+        for (covered, threshold) in self.covered.iter_mut().zip(THRESHOLDS.iter()) {
+            if depth >= *threshold {
+                *covered += n;
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.positions += other.positions;
+        self.sum += other.sum;
+        self.covered
+            .iter_mut()
+            .zip(other.covered.iter())
+            .for_each(|(a, b)| *a += b);
+    }
+}
+
+/// Depth of coverage of a file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Depth {
+    /// Summary of each reference sequence
+    pub references: Vec<ReferenceDepth>,
+
+    /// Number of positions by depth
+    pub histogram: Vec<u64>,
+
+    /// Summary of each target, in order of [bed::Targets::targets], empty without targets
+    pub targets: Vec<ReferenceDepth>,
+
+    /// Number of positions in targets by depth, overlapping targets are count once
+    pub target_histogram: Vec<u64>,
+
+    /// Sum of depth of all positions in targets, overlapping targets are count once
+    pub target_sum: u64,
+}
+
+impl Depth {
+    /// Merge other in self, reference sequences are match by name
+    pub fn merge(&mut self, other: &Self) {
+        for (i, reference) in other.references.iter().enumerate() {
+            let position = if self
+                .references
+                .get(i)
+                .map_or(false, |r| r.name == reference.name)
+            {
+                Some(i)
+            } else {
+                self.references
+                    .iter()
+                    .position(|r| r.name == reference.name)
+            };
+
+            match position {
+                Some(position) => self.references[position].merge(reference),
+                None => self.references.push(reference.clone()),
+            }
+        }
+
+        if self.histogram.len() < other.histogram.len() {
+            self.histogram.resize(other.histogram.len(), 0);
+        }
+        self.histogram
+            .iter_mut()
+            .zip(other.histogram.iter())
+            .for_each(|(a, b)| *a += b);
+
+        // All files use same targets
+        if self.targets.is_empty() {
+            self.targets = other.targets.clone();
+        } else {
+            self.targets
+                .iter_mut()
+                .zip(other.targets.iter())
+                .for_each(|(a, b)| a.merge(b));
+        }
+
+        if self.target_histogram.len() < other.target_histogram.len() {
+            self.target_histogram
+                .resize(other.target_histogram.len(), 0);
+        }
+        self.target_histogram
+            .iter_mut()
+            .zip(other.target_histogram.iter())
+            .for_each(|(a, b)| *a += b);
+
+        self.target_sum += other.target_sum;
+    }
+}
+
+/// Depth of each input file, shared between a reader and metrics
+///
+/// Depth of a file is set when reader reach end of file, a file not sorted by coordinate
+/// have no depth.
+#[derive(Debug, Clone, Default)]
+pub struct Depths(Arc<Mutex<HashMap<usize, Depth>>>);
+
+impl Depths {
+    /// Create an empty Depths
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set depth of file index
+    pub fn insert(&self, index: usize, depth: Depth) {
+        self.0.lock().unwrap().insert(index, depth);
+    }
+
+    /// Get depth of file index
+    pub fn get(&self, index: usize) -> Option<Depth> {
+        self.0.lock().unwrap().get(&index).cloned()
+    }
+}
+
+/// Depth of targets, positions must be add in increasing order by reference sequence
+///
+/// Only positions add are count in length of targets, positions not read (outside of regions,
+/// on reference sequence absent of file) aren't count
+struct TargetSweep {
+    targets: Arc<bed::Targets>,
+    chrom: Option<usize>,
+    cursor: usize,
+    merged_cursor: usize,
+    depths: Vec<ReferenceDepth>,
+    histogram: Vec<u64>,
+    sum: u64,
+}
+
+impl TargetSweep {
+    fn new(targets: Arc<bed::Targets>) -> Self {
+        Self {
+            depths: targets
+                .targets()
+                .iter()
+                .map(|t| ReferenceDepth::new(t.name.clone()))
+                .collect(),
+            targets,
+            chrom: None,
+            cursor: 0,
+            merged_cursor: 0,
+            histogram: vec![0; MAX_DEPTH + 1],
+            sum: 0,
+        }
+    }
+
+    /// Start a new reference sequence
+    fn set_reference(&mut self, name: &str) {
+        self.chrom = self.targets.chrom_index(name);
+        self.cursor = 0;
+        self.merged_cursor = 0;
+    }
+
+    /// Add n positions with depth from start
+    fn add(&mut self, start: u64, depth: u64, n: u64) {
+        let chrom = match self.chrom {
+            Some(chrom) => &self.targets.chroms()[chrom],
+            None => return,
+        };
+        let end = start + n;
+
+        while self.cursor < chrom.intervals.len() && chrom.max_ends[self.cursor] <= start {
+            self.cursor += 1;
+        }
+        for (i, (t_start, t_end)) in chrom.intervals[self.cursor..].iter().enumerate() {
+            if *t_start >= end {
+                break;
+            }
+
+            let overlap = (*t_end).min(end).saturating_sub((*t_start).max(start));
+            if overlap != 0 {
+                self.depths[chrom.offset + self.cursor + i].add(depth, overlap);
+            }
+        }
+
+        while self.merged_cursor < chrom.merged.len() && chrom.merged[self.merged_cursor].1 <= start
+        {
+            self.merged_cursor += 1;
+        }
+        for (t_start, t_end) in chrom.merged[self.merged_cursor..].iter() {
+            if *t_start >= end {
+                break;
+            }
+
+            let overlap = (*t_end).min(end) - (*t_start).max(start);
+            self.histogram[(depth as usize).min(MAX_DEPTH)] += overlap;
+            self.sum += depth * overlap;
+        }
+    }
+}
+
+/// Positions where depth is count, use when only regions of a file are read
+struct RegionMask {
+    intervals: Vec<Vec<(u64, u64)>>,
+    cursor: usize,
+}
+
+impl RegionMask {
+    fn new(intervals: &[region::Interval], nb_references: usize) -> Self {
+        let mut by_reference: Vec<Vec<(u64, u64)>> = vec![Vec::new(); nb_references];
+        for (id, start, end) in intervals.iter().filter(|(id, _, _)| *id < nb_references) {
+            by_reference[*id].push((*start as u64 - 1, *end as u64));
+        }
+
+        for intervals in by_reference.iter_mut() {
+            intervals.sort_unstable();
+
+            let mut merged: Vec<(u64, u64)> = Vec::new();
+            for (start, end) in intervals.iter() {
+                match merged.last_mut() {
+                    Some(last) if *start <= last.1 => last.1 = last.1.max(*end),
+                    _ => merged.push((*start, *end)),
+                }
+            }
+            *intervals = merged;
+        }
+
+        Self {
+            intervals: by_reference,
+            cursor: 0,
+        }
+    }
+
+    /// Start a new reference sequence
+    fn set_reference(&mut self) {
+        self.cursor = 0;
+    }
+
+    /// Parts of start..end in regions of reference id, start must increase between call on same
+    /// reference
+    fn clip(&mut self, id: usize, start: u64, end: u64) -> impl Iterator<Item = (u64, u64)> + '_ {
+        let intervals = &self.intervals[id];
+
+        while self.cursor < intervals.len() && intervals[self.cursor].1 <= start {
+            self.cursor += 1;
+        }
+
+        intervals[self.cursor..]
+            .iter()
+            .take_while(move |(s, _)| *s < end)
+            .map(move |(s, e)| ((*s).max(start), (*e).min(end)))
+    }
+
+    /// Number of positions in regions of reference id
+    fn length(&self, id: usize) -> u64 {
+        self.intervals[id].iter().map(|(s, e)| e - s).sum()
+    }
+}
+
+/// Compute depth of one file, records must be observe in file order
+pub struct Sweep {
+    path: String,
+    lengths: Vec<u64>,
+    references: Vec<ReferenceDepth>,
+    histogram: Vec<u64>,
+    sorted: bool,
+    current: Option<usize>,
+    last_start: u64,
+    position: u64,
+    depth: i64,
+    deltas: VecDeque<i64>,
+    targets: Option<TargetSweep>,
+    regions: Option<RegionMask>,
+}
+
+impl Sweep {
+    /// Create a Sweep for file at path with its reference sequences dictionary
+    pub fn new(path: &str, dictionary: &dictionary::Dictionary) -> Self {
+        Self {
+            path: path.to_string(),
+            lengths: dictionary.iter().map(|(_, length)| *length).collect(),
+            references: dictionary
+                .iter()
+                .map(|(name, _)| ReferenceDepth::new(name.clone()))
+                .collect(),
+            histogram: vec![0; MAX_DEPTH + 1],
+            sorted: true,
+            current: None,
+            last_start: 0,
+            position: 0,
+            depth: 0,
+            deltas: VecDeque::new(),
+            targets: None,
+            regions: None,
+        }
+    }
+
+    /// Compute depth of targets too
+    pub fn with_targets(mut self, targets: Arc<bed::Targets>) -> Self {
+        self.targets = Some(TargetSweep::new(targets));
+
+        self
+    }
+
+    /// Count only depth of positions in intervals, reference sequences without interval are
+    /// remove of result
+    pub fn with_regions(mut self, intervals: &[region::Interval]) -> Self {
+        self.regions = Some(RegionMask::new(intervals, self.references.len()));
+
+        self
+    }
+
+    /// Update depth with a record, unmapped, secondary, QC-fail and duplicate records are
+    /// ignored, like mosdepth
+    pub fn observe(&mut self, record: &noodles::bam::Record) {
+        let flags = record.flags();
+        if !self.sorted
+            || flags.is_unmapped()
+            || flags.is_secondary()
+            || flags.is_qc_fail()
+            || flags.is_duplicate()
+        {
+            return;
+        }
+
+        let (id, start) = match (record.reference_sequence_id(), record.position()) {
+            (Some(id), Some(position)) => (i32::from(id) as usize, i32::from(position) as u64 - 1),
+            _ => return,
+        };
+
+        if id >= self.references.len() {
+            return;
+        }
+
+        if self.current != Some(id) {
+            if self.current.map_or(false, |current| id < current) {
+                return self.unsorted();
+            }
+
+            self.close_reference();
+            let first = self.current.map_or(0, |current| current + 1);
+            (first..id).for_each(|i| self.fill_reference(i));
+
+            self.current = Some(id);
+            self.position = 0;
+            if let Some(regions) = self.regions.as_mut() {
+                regions.set_reference();
+            }
+            if let Some(targets) = self.targets.as_mut() {
+                targets.set_reference(&self.references[id].name);
+            }
+        } else if start < self.last_start {
+            return self.unsorted();
+        }
+        self.last_start = start;
+
+        self.flush(start);
+
+        let mut position = start;
+        for op in record.cigar().ops().filter_map(|op| op.ok()) {
+            match op.kind() {
+                Kind::Match | Kind::SeqMatch | Kind::SeqMismatch => {
+                    self.add_delta(position, 1);
+                    position += op.len() as u64;
+                    self.add_delta(position, -1);
+                }
+                Kind::Deletion | Kind::Skip => position += op.len() as u64,
+                _ => (),
+            }
+        }
+    }
+
+    /// Close sweep and get depth, None if file isn't sorted by coordinate
+    pub fn finish(mut self) -> Option<Depth> {
+        if !self.sorted {
+            return None;
+        }
+
+        self.close_reference();
+        let first = self.current.map_or(0, |current| current + 1);
+        (first..self.references.len()).for_each(|i| self.fill_reference(i));
+
+        let (targets, target_histogram, target_sum) = match self.targets {
+            Some(targets) => (targets.depths, targets.histogram, targets.sum),
+            None => Default::default(),
+        };
+
+        let references = match self.regions {
+            Some(regions) => self
+                .references
+                .into_iter()
+                .enumerate()
+                .filter(|(id, _)| !regions.intervals[*id].is_empty())
+                .map(|(_, reference)| reference)
+                .collect(),
+            None => self.references,
+        };
+
+        Some(Depth {
+            references,
+            histogram: self.histogram,
+            targets,
+            target_histogram,
+            target_sum,
+        })
+    }
+
+    fn unsorted(&mut self) {
+        log::warn!(
+            "File {} isn't sorted by coordinate, depth of coverage isn't compute",
+            self.path
+        );
+        self.sorted = false;
+        self.deltas.clear();
+    }
+
+    fn add_delta(&mut self, position: u64, delta: i64) {
+        let index = (position - self.position) as usize;
+        if index >= self.deltas.len() {
+            self.deltas.resize(index + 1, 0);
+        }
+
+        self.deltas[index] += delta;
+    }
+
+    /// Add n positions with depth in current reference from current position, only positions
+    /// in regions are count
+    fn add(&mut self, depth: u64, n: u64) {
+        let current = match self.current {
+            Some(current) => current,
+            None => return,
+        };
+        let Self {
+            references,
+            histogram,
+            targets,
+            regions,
+            position,
+            ..
+        } = self;
+
+        let mut count = |start: u64, end: u64| {
+            references[current].add(depth, end - start);
+            histogram[(depth as usize).min(MAX_DEPTH)] += end - start;
+            if let Some(targets) = targets.as_mut() {
+                targets.add(start, depth, end - start);
+            }
+        };
+
+        match regions.as_mut() {
+            Some(regions) => regions
+                .clip(current, *position, *position + n)
+                .for_each(|(start, end)| count(start, end)),
+            None => count(*position, *position + n),
+        }
+    }
+
+    /// Compute depth of all positions before end, positions after end of current reference are
+    /// ignored
+    ///
+    /// Positions with same depth are add in one step, a run end at next non-zero delta
+    fn flush(&mut self, end: u64) {
+        let end = self
+            .current
+            .map_or(end, |current| end.min(self.lengths[current]));
+        while self.position < end {
+            if let Some(delta) = self.deltas.pop_front() {
+                self.depth += delta;
+            }
+
+            // deltas after end of deque are zero
+            let max = end - self.position;
+            let run = 1 + self
+                .deltas
+                .iter()
+                .take(max as usize - 1)
+                .position(|delta| *delta != 0)
+                .map_or(max - 1, |index| index as u64);
+            self.deltas
+                .drain(..(run as usize - 1).min(self.deltas.len()));
+
+            self.add(self.depth as u64, run);
+            self.position += run;
+        }
+    }
+
+    /// Compute depth of remaining positions of current reference, depth after reference end is
+    /// drop
+    fn close_reference(&mut self) {
+        if let Some(current) = self.current {
+            self.flush(self.lengths[current]);
+            self.depth = 0;
+            self.deltas.clear();
+        }
+    }
+
+    /// Add all positions of a reference without alignment
+    fn fill_reference(&mut self, id: usize) {
+        let length = self
+            .regions
+            .as_ref()
+            .map_or(self.lengths[id], |regions| regions.length(id));
+        self.references[id].add(0, length);
+        self.histogram[0] += length;
+        if let Some(targets) = self.targets.as_mut() {
+            targets.set_reference(&self.references[id].name);
+            match self.regions.as_ref() {
+                Some(regions) => regions.intervals[id]
+                    .iter()
+                    .for_each(|(start, end)| targets.add(*start, 0, end - start)),
+                None => targets.add(0, 0, self.lengths[id]),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use noodles::sam::record::Flags;
+
+    const NAMES: [&str; 3] = ["chr1", "chr2", "chr3"];
+
+    fn record(flags: Flags, reference_id: usize, start: i32, cigar: &str) -> noodles::bam::Record {
+        let header = noodles::sam::Header::builder()
+            .add_reference_sequence(
+                noodles::sam::header::ReferenceSequence::new(NAMES[0].parse().unwrap(), 20)
+                    .unwrap(),
+            )
+            .add_reference_sequence(
+                noodles::sam::header::ReferenceSequence::new(NAMES[1].parse().unwrap(), 10)
+                    .unwrap(),
+            )
+            .add_reference_sequence(
+                noodles::sam::header::ReferenceSequence::new(NAMES[2].parse().unwrap(), 5).unwrap(),
+            )
+            .build();
+
+        let record = noodles::sam::Record::builder()
+            .set_flags(flags)
+            .set_reference_sequence_name(NAMES[reference_id].parse().unwrap())
+            .set_position(start.try_into().unwrap())
+            .set_cigar(cigar.parse().unwrap())
+            .build()
+            .unwrap();
+
+        noodles::bam::Record::try_from_sam_record(header.reference_sequences(), &record).unwrap()
+    }
+
+    fn dictionary() -> dictionary::Dictionary {
+        vec![
+            ("chr1".to_string(), 20),
+            ("chr2".to_string(), 10),
+            ("chr3".to_string(), 5),
+        ]
+    }
+
+    #[test]
+    fn sweep() {
+        let mut sweep = Sweep::new("test.bam", &dictionary());
+
+        sweep.observe(&record(Flags::empty(), 0, 1, "10M"));
+        sweep.observe(&record(Flags::empty(), 0, 6, "10M"));
+        sweep.observe(&record(Flags::DUPLICATE, 0, 6, "10M"));
+        sweep.observe(&record(Flags::UNMAPPED, 0, 6, "10M"));
+        sweep.observe(&record(Flags::empty(), 2, 2, "3S2M2D2="));
+
+        let depth = sweep.finish().unwrap();
+
+        let mut chr1 = ReferenceDepth::new("chr1".to_string());
+        chr1.positions = 20;
+        chr1.sum = 20;
+        chr1.covered = [15, 0, 0];
+        assert_eq!(depth.references[0], chr1);
+
+        assert_eq!(depth.references[1].positions, 10);
+        assert_eq!(depth.references[1].sum, 0);
+
+        // Deletion isn't cover, alignment after reference end isn't count
+        assert_eq!(depth.references[2].positions, 5);
+        assert_eq!(depth.references[2].sum, 2);
+
+        assert_eq!(depth.histogram.len(), MAX_DEPTH + 1);
+        assert_eq!(depth.histogram[..3], [5 + 10 + 3, 10 + 2, 5]);
+        assert_eq!(depth.histogram.iter().sum::<u64>(), 35);
+    }
+
+    #[test]
+    fn sweep_runs() {
+        let mut sweep = Sweep::new("test.bam", &dictionary());
+
+        sweep.observe(&record(Flags::empty(), 0, 1, "4M"));
+        sweep.observe(&record(Flags::empty(), 0, 1, "2M4D2M"));
+        sweep.observe(&record(Flags::empty(), 0, 3, "4M"));
+        sweep.observe(&record(Flags::empty(), 0, 18, "2M"));
+        sweep.observe(&record(Flags::empty(), 1, 9, "1M"));
+
+        let depth = sweep.finish().unwrap();
+
+        // chr1 depth: 2 2 2 2 1 1 1 1 0 ... 0 1 1 0
+        assert_eq!(depth.references[0].positions, 20);
+        assert_eq!(depth.references[0].sum, 4 * 2 + 2 + 2 + 2);
+        assert_eq!(depth.references[0].covered, [10, 0, 0]);
+        assert_eq!(depth.references[1].sum, 1);
+        assert_eq!(depth.histogram[..3], [10 + 9 + 5, 6 + 1, 4]);
+    }
+
+    #[test]
+    fn targets() {
+        let target = |chrom: &str, start: u64, end: u64, name: &str| bed::Target {
+            chrom: chrom.to_string(),
+            start,
+            end,
+            name: name.to_string(),
+        };
+        let targets = bed::Targets::new(vec![
+            target("chr1", 0, 10, "a"),
+            target("chr1", 5, 15, "b"),
+            target("chr2", 0, 4, "c"),
+            target("chrX", 0, 10, "d"),
+        ]);
+
+        let mut sweep = Sweep::new("test.bam", &dictionary()).with_targets(Arc::new(targets));
+        sweep.observe(&record(Flags::empty(), 0, 1, "10M"));
+        sweep.observe(&record(Flags::empty(), 0, 6, "10M"));
+
+        let depth = sweep.finish().unwrap();
+
+        let sums: Vec<(u64, u64, u64)> = depth
+            .targets
+            .iter()
+            .map(|t| (t.positions, t.sum, t.covered[0]))
+            .collect();
+        assert_eq!(sums, vec![(10, 15, 10), (10, 15, 10), (4, 0, 0), (0, 0, 0)]);
+
+        assert_eq!(depth.target_histogram[..3], [4, 10, 5]);
+        assert_eq!(depth.target_sum, 20);
+
+        let mut all = depth.clone();
+        all.merge(&depth);
+        assert_eq!(all.targets[0].positions, 20);
+        assert_eq!(all.targets[0].sum, 30);
+        assert_eq!(all.target_sum, 40);
+    }
+
+    #[test]
+    fn regions() {
+        let mut sweep =
+            Sweep::new("test.bam", &dictionary()).with_regions(&[(0, 6, 12), (0, 3, 8), (2, 5, 5)]);
+        sweep.observe(&record(Flags::empty(), 0, 1, "10M"));
+        sweep.observe(&record(Flags::empty(), 0, 6, "10M"));
+
+        let depth = sweep.finish().unwrap();
+
+        let mut chr1 = ReferenceDepth::new("chr1".to_string());
+        chr1.positions = 10;
+        chr1.sum = 3 + 10 + 2;
+        chr1.covered = [10, 0, 0];
+        assert_eq!(depth.references[0], chr1);
+
+        assert_eq!(depth.references.len(), 2);
+        assert_eq!(depth.references[1].name, "chr3");
+        assert_eq!(depth.references[1].positions, 1);
+
+        assert_eq!(depth.histogram[..3], [1, 5, 5]);
+    }
+
+    #[test]
+    fn regions_targets() {
+        let target = |chrom: &str, start: u64, end: u64, name: &str| bed::Target {
+            chrom: chrom.to_string(),
+            start,
+            end,
+            name: name.to_string(),
+        };
+        let targets = bed::Targets::new(vec![
+            target("chr1", 0, 10, "a"),
+            target("chr1", 12, 18, "b"),
+            target("chr2", 0, 4, "c"),
+            target("chr3", 0, 5, "d"),
+        ]);
+
+        let mut sweep = Sweep::new("test.bam", &dictionary())
+            .with_regions(&[(0, 6, 15), (2, 1, 5)])
+            .with_targets(Arc::new(targets));
+        sweep.observe(&record(Flags::empty(), 0, 1, "10M"));
+        sweep.observe(&record(Flags::empty(), 0, 6, "10M"));
+
+        let depth = sweep.finish().unwrap();
+
+        // Only positions of targets in regions are count
+        let sums: Vec<(u64, u64, u64)> = depth
+            .targets
+            .iter()
+            .map(|t| (t.positions, t.sum, t.covered[0]))
+            .collect();
+        assert_eq!(sums, vec![(5, 10, 5), (3, 3, 3), (0, 0, 0), (5, 0, 0)]);
+
+        assert_eq!(depth.target_histogram[..3], [5, 3, 5]);
+        assert_eq!(depth.target_sum, 13);
+    }
+
+    #[test]
+    fn unsorted() {
+        let mut sweep = Sweep::new("test.bam", &dictionary());
+        sweep.observe(&record(Flags::empty(), 0, 5, "2M"));
+        sweep.observe(&record(Flags::empty(), 0, 2, "2M"));
+        assert!(sweep.finish().is_none());
+
+        let mut sweep = Sweep::new("test.bam", &dictionary());
+        sweep.observe(&record(Flags::empty(), 1, 5, "2M"));
+        sweep.observe(&record(Flags::empty(), 0, 8, "2M"));
+        assert!(sweep.finish().is_none());
+    }
+
+    #[test]
+    fn merge() {
+        let mut sweep = Sweep::new("a.bam", &dictionary());
+        sweep.observe(&record(Flags::empty(), 1, 1, "2M"));
+        let mut depth = sweep.finish().unwrap();
+
+        // Second file contains only chr2, record is on chr1 of header of test
+        let mut sweep = Sweep::new("b.bam", &vec![("chr2".to_string(), 10)]);
+        sweep.observe(&record(Flags::empty(), 0, 1, "2M"));
+        depth.merge(&sweep.finish().unwrap());
+
+        assert_eq!(depth.references.len(), 3);
+        assert_eq!(depth.references[1].positions, 20);
+        assert_eq!(depth.references[1].sum, 4);
+        assert_eq!(depth.references[1].covered, [4, 0, 0]);
+        assert_eq!(depth.histogram[..2], [41, 4]);
+    }
+
+    #[test]
+    fn depths() {
+        let depths = Depths::new();
+        let other = depths.clone();
+
+        depths.insert(2, Depth::default());
+
+        assert!(other.get(0).is_none());
+        assert_eq!(other.get(2), Some(Depth::default()));
+    }
+}
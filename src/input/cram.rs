@@ -0,0 +1,301 @@
+//! Parse cram
+
+/* std use */
+
+/* crates use */
+use anyhow::Context;
+
+/* project use */
+use crate::error;
+use crate::input::bgzf;
+use crate::input::dictionary;
+use crate::input::stream;
+
+/// Cram reader and header of file
+type Input = (
+    noodles::cram::Reader<std::io::BufReader<stream::Stream>>,
+    noodles::sam::Header,
+);
+
+/// Read all sequences of a fasta reference
+pub fn read_reference(path: &str) -> anyhow::Result<Vec<noodles::fasta::Record>> {
+    log::debug!("Read reference {}", path);
+
+    let mut reader = noodles::fasta::Reader::new(std::io::BufReader::new(bgzf::get_reader(
+        stream::open(path)?,
+    )?));
+
+    Ok(reader.records().collect::<std::io::Result<Vec<_>>>()?)
+}
+
+/// Open a cram
+fn open(source: stream::Source, buffer_size: usize) -> anyhow::Result<Input> {
+    log::debug!("Open file {}", source.path());
+
+    let mut reader = noodles::cram::Reader::new(std::io::BufReader::with_capacity(
+        buffer_size,
+        source.open()?,
+    ));
+    reader.read_file_definition()?;
+    let header = reader.read_file_header()?.parse()?;
+
+    Ok((reader, header))
+}
+
+/// Struct to parse Cram file, records are convert in bam record
+///
+/// Sequence of mapped records are rebuild from reference, without reference only unmapped
+/// records can be read
+pub struct Cram {
+    buffer_size: usize,
+    sources: Vec<stream::Source>,
+    current_index: usize,
+    reference: Vec<noodles::fasta::Record>,
+    records: std::vec::IntoIter<anyhow::Result<noodles::bam::Record>>,
+    current_input: Option<Input>,
+    dictionaries: dictionary::Dictionaries,
+}
+
+impl Cram {
+    /// Create a Cram struct, with inputs path, reference sequences and size of read buffer
+    /// If last file of inputs can't be open this function return an anyhow::Error
+    /// Other inputs can't be open is ignored, an error message is put in log
+    ///
+    /// Each record is tagged with the index of its source path in inputs
+    pub fn new<S: Into<stream::Source>>(
+        inputs: Vec<S>,
+        reference: Vec<noodles::fasta::Record>,
+        buffer_size: usize,
+    ) -> anyhow::Result<Self> {
+        let mut inputs: Vec<stream::Source> = inputs.into_iter().map(Into::into).collect();
+        let first = inputs
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("inputs is empty"))?;
+        let first_path = first.path().to_string();
+
+        let current_input =
+            open(first, buffer_size).with_context(|| error::Error::CramOpenError {
+                path: first_path.clone(),
+            })?;
+
+        let dictionaries = dictionary::Dictionaries::new();
+        dictionaries.insert(
+            inputs.len(),
+            dictionary::from_header(current_input.1.reference_sequences()),
+        );
+
+        Ok(Self {
+            buffer_size,
+            current_index: inputs.len(),
+            sources: inputs,
+            reference,
+            records: Vec::new().into_iter(),
+            current_input: Some(current_input),
+            dictionaries,
+        })
+    }
+
+    /// Reference sequences dictionary of each file, a file dictionary is set when file is open
+    pub fn dictionaries(&self) -> dictionary::Dictionaries {
+        self.dictionaries.clone()
+    }
+
+    /// Read next data container and convert all its records, return false at end of file
+    fn read_container(&mut self) -> anyhow::Result<bool> {
+        let (reader, header) = match self.current_input.as_mut() {
+            Some(input) => input,
+            None => return Ok(false),
+        };
+
+        let container = match reader.read_data_container()? {
+            Some(container) => container,
+            None => return Ok(false),
+        };
+
+        let mut records = Vec::new();
+        for slice in container.slices() {
+            let slice_records = slice.records(container.compression_header())?;
+
+            for record in slice.resolve_mates(slice_records) {
+                records.push(
+                    record
+                        .try_into_sam_record(
+                            &self.reference,
+                            header.reference_sequences(),
+                            container.compression_header(),
+                        )
+                        .and_then(|r| {
+                            noodles::bam::Record::try_from_sam_record(
+                                header.reference_sequences(),
+                                &r,
+                            )
+                        })
+                        .map_err(|e| anyhow::Error::new(e).context(error::Error::CramParsingError)),
+                );
+            }
+        }
+
+        self.records = records.into_iter();
+
+        Ok(true)
+    }
+}
+
+impl Iterator for Cram {
+    type Item = (usize, anyhow::Result<noodles::bam::Record>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(record) = self.records.next() {
+            return Some((self.current_index, record));
+        }
+
+        match self.read_container() {
+            Ok(true) => self.next(),
+            Ok(false) => {
+                self.current_input = None;
+                if let Some(new_source) = self.sources.pop() {
+                    let new_path = new_source.path().to_string();
+                    self.current_index = self.sources.len();
+                    match open(new_source, self.buffer_size) {
+                        Ok(new_input) => {
+                            self.dictionaries.insert(
+                                self.current_index,
+                                dictionary::from_header(new_input.1.reference_sequences()),
+                            );
+                            self.current_input = Some(new_input);
+                            self.next()
+                        }
+                        Err(e) => Some((
+                            self.current_index,
+                            Err(e.context(error::Error::CramOpenError {
+                                path: new_path.clone(),
+                            })),
+                        )),
+                    }
+                } else {
+                    None // No new file end of iterator
+                }
+            }
+            Err(e) => {
+                // Cram stream can't be resynchronized after an error, skip rest of file
+                self.current_input = None;
+                Some((
+                    self.current_index,
+                    Err(e.context(error::Error::CramParsingError)),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    fn create_cram_file() -> (NamedTempFile, String) {
+        let tmp_file = tempfile::NamedTempFile::new().unwrap();
+
+        {
+            let mut writer = noodles::cram::Writer::new(tmp_file.reopen().unwrap(), Vec::new());
+
+            let header = noodles::sam::Header::builder().build();
+            writer.write_file_definition().unwrap();
+            writer.write_file_header(&header).unwrap();
+
+            for (i, seq) in [&b"ACTG"[..], b"ACTGACTG", b"AACACGTGAGTCCGCACACCGGACG"]
+                .iter()
+                .enumerate()
+            {
+                let record = noodles::cram::Record::builder()
+                    .set_id(i as i64)
+                    .set_bam_flags(noodles::sam::record::Flags::UNMAPPED)
+                    .set_read_name(format!("{}", i + 1).into_bytes())
+                    .set_alignment_start(noodles::sam::record::Position::try_from(1).unwrap())
+                    .set_read_length(seq.len())
+                    .set_bases(seq.to_vec())
+                    .set_quality_scores(vec![30; seq.len()])
+                    .build();
+                writer.write_record(record).unwrap();
+            }
+
+            writer.try_finish().unwrap();
+        }
+
+        let path = tmp_file.path().to_str().unwrap().to_string();
+
+        (tmp_file, path)
+    }
+
+    #[test]
+    fn read_reference_() {
+        let mut tmp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(tmp_file, ">chr1\nACGT\nACGT\n>chr2\nTTTT").unwrap();
+
+        let reference = read_reference(tmp_file.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(reference.len(), 2);
+        assert_eq!(reference[0].name(), "chr1");
+        assert_eq!(reference[0].sequence().as_ref(), b"ACGTACGT");
+    }
+
+    #[test]
+    fn open_cram_file() {
+        let (tmp_file, path) = create_cram_file();
+
+        assert!(open(path.as_str().into(), 10).is_ok());
+
+        tmp_file.close().unwrap();
+
+        assert!(open(path.as_str().into(), 10).is_err());
+    }
+
+    #[test]
+    fn iterate_over_cram() {
+        let (_e, path) = create_cram_file();
+
+        let mut reader = Cram::new(vec![path], Vec::new(), 10).unwrap();
+
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
+            [18, 132]
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
+            [18, 132, 18, 132]
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
+            [17, 33, 36, 132, 20, 130, 36, 33, 33, 34, 68, 18, 64]
+        );
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn iterate_over_two_cram_notfile() {
+        let (_file1, path1) = create_cram_file();
+        let (_file2, path2) = create_cram_file();
+        let (file3, path3) = create_cram_file();
+
+        file3.close().unwrap();
+
+        let reader = Cram::new(vec![path3, path2, path1], Vec::new(), 10).unwrap();
+        let records: Vec<(usize, bool)> = reader.map(|(i, r)| (i, r.is_ok())).collect();
+
+        assert_eq!(
+            records,
+            vec![
+                (2, true),
+                (2, true),
+                (2, true),
+                (1, true),
+                (1, true),
+                (1, true),
+                (0, false)
+            ]
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Detect format of input file from its content
+
+/* std use */
+use std::io::Read;
+
+/* crates use */
+use anyhow::Context;
+
+/* project use */
+use crate::error;
+use crate::input::stream;
+
+/// Number of decompressed bytes use to detect format
+const PREFIX_LEN: usize = 4096;
+
+/// Number of raw bytes read to detect format
+const RAW_PREFIX_LEN: usize = 1 << 16;
+
+/// Minimal number of field in a sam record line
+const SAM_NB_FIELDS: usize = 11;
+
+/// Format of an input file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    Fastq,
+    Fasta,
+    Sam,
+    Bam,
+    Cram,
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Format::Fastq => write!(f, "fastq"),
+            Format::Fasta => write!(f, "fasta"),
+            Format::Sam => write!(f, "sam"),
+            Format::Bam => write!(f, "bam"),
+            Format::Cram => write!(f, "cram"),
+        }
+    }
+}
+
+/// Found format from first bytes of decompressed content
+pub fn from_bytes(prefix: &[u8]) -> Option<Format> {
+    if prefix.starts_with(b"BAM\x01") {
+        Some(Format::Bam)
+    } else if prefix.starts_with(b"CRAM") {
+        Some(Format::Cram)
+    } else if [&b"@HD\t"[..], b"@SQ\t", b"@RG\t", b"@PG\t", b"@CO\t"]
+        .iter()
+        .any(|tag| prefix.starts_with(tag))
+    {
+        Some(Format::Sam)
+    } else if prefix.starts_with(b"@") {
+        Some(Format::Fastq)
+    } else if prefix.starts_with(b">") {
+        Some(Format::Fasta)
+    } else {
+        // sam without header
+        let line = prefix.split(|c| *c == b'\n').next()?;
+        if line.split(|c| *c == b'\t').count() >= SAM_NB_FIELDS {
+            Some(Format::Sam)
+        } else {
+            None
+        }
+    }
+}
+
+/// Detect format of source, compression (gzip, bgzf, bzip2, xz) is detect by niffler
+///
+/// Source is return to be read, if it's a stream (stdin, named pipe, already open) it's return
+/// open with consumed bytes put back in front
+pub fn detect(source: stream::Source) -> anyhow::Result<(Format, stream::Source)> {
+    let path = source.path().to_string();
+    let reopen = matches!(source, stream::Source::Path(_)) && !stream::is_stream(&path);
+
+    let mut reader = source
+        .open()
+        .with_context(|| error::Error::UnknownFormat { path: path.clone() })?;
+
+    let mut raw = Vec::with_capacity(RAW_PREFIX_LEN);
+    reader
+        .by_ref()
+        .take(RAW_PREFIX_LEN as u64)
+        .read_to_end(&mut raw)
+        .with_context(|| error::Error::UnknownFormat { path: path.clone() })?;
+
+    let (prefix, compression) = decompress(&raw);
+
+    let format =
+        from_bytes(&prefix).ok_or_else(|| error::Error::UnknownFormat { path: path.clone() })?;
+
+    log::info!(
+        "Detect format {} with compression {:?} for file {}",
+        format,
+        compression,
+        path
+    );
+
+    let source = if reopen {
+        stream::Source::Path(path)
+    } else {
+        stream::Source::Open(path, Box::new(std::io::Cursor::new(raw).chain(reader)))
+    };
+
+    Ok((format, source))
+}
+
+/// Decompress begin of raw content, content too short to be compressed is return as is
+fn decompress(raw: &[u8]) -> (Vec<u8>, niffler::compression::Format) {
+    match niffler::get_reader(Box::new(std::io::Cursor::new(raw.to_vec()))) {
+        Ok((reader, compression)) => {
+            let mut prefix = Vec::with_capacity(PREFIX_LEN);
+
+            // raw content could be truncate in a compressed block, bytes read before error are keep
+            let _ = reader.take(PREFIX_LEN as u64).read_to_end(&mut prefix);
+
+            (prefix, compression)
+        }
+        Err(_) => (raw.to_vec(), niffler::compression::Format::No),
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use std::io::Write;
+
+    #[test]
+    fn from_bytes_() {
+        assert_eq!(from_bytes(b"BAM\x01\x00\x00"), Some(Format::Bam));
+        assert_eq!(from_bytes(b"CRAM\x03\x00"), Some(Format::Cram));
+        assert_eq!(from_bytes(b"@HD\tVN:1.6\n"), Some(Format::Sam));
+        assert_eq!(from_bytes(b"@SQ\tSN:chr1\tLN:10\n"), Some(Format::Sam));
+        assert_eq!(from_bytes(b"@read1\nACGT\n+\nIIII\n"), Some(Format::Fastq));
+        assert_eq!(from_bytes(b">read1\nACGT\n"), Some(Format::Fasta));
+        assert_eq!(
+            from_bytes(b"r1\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\tIIII\n"),
+            Some(Format::Sam)
+        );
+        assert_eq!(from_bytes(b"type,file\n"), None);
+        assert_eq!(from_bytes(b""), None);
+    }
+
+    #[test]
+    fn detect_compressed() {
+        let mut tmp_file = tempfile::NamedTempFile::new().unwrap();
+
+        {
+            let mut writer = niffler::get_writer(
+                Box::new(tmp_file.reopen().unwrap()),
+                niffler::compression::Format::Gzip,
+                niffler::compression::Level::One,
+            )
+            .unwrap();
+            writer.write_all(b"@read1\nACGT\n+\nIIII\n").unwrap();
+        }
+
+        assert_eq!(
+            detect(tmp_file.path().to_str().unwrap().into()).unwrap().0,
+            Format::Fastq
+        );
+
+        tmp_file.as_file_mut().set_len(0).unwrap();
+        writeln!(tmp_file, ">read1\nACGT").unwrap();
+
+        assert_eq!(
+            detect(tmp_file.path().to_str().unwrap().into()).unwrap().0,
+            Format::Fasta
+        );
+    }
+
+    #[test]
+    fn detect_unknow() {
+        let mut tmp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(tmp_file, "type,file,at").unwrap();
+
+        assert!(detect(tmp_file.path().to_str().unwrap().into()).is_err());
+        assert!(detect("not_exist.fastq".into()).is_err());
+    }
+
+    #[test]
+    fn detect_stream() {
+        let content = b"@read1\nACGT\n+\nIIII\n".repeat(10000);
+
+        let (format, source) = detect(stream::Source::Open(
+            "pipe".to_string(),
+            Box::new(std::io::Cursor::new(content.clone())),
+        ))
+        .unwrap();
+        assert_eq!(format, Format::Fastq);
+        assert_eq!(source.path(), "pipe");
+
+        let mut read = Vec::new();
+        source.open().unwrap().read_to_end(&mut read).unwrap();
+        assert_eq!(read, content);
+    }
+
+    #[test]
+    fn decompress_truncated() {
+        let mut compressed = Vec::new();
+        {
+            let mut writer = niffler::get_writer(
+                Box::new(&mut compressed),
+                niffler::compression::Format::Gzip,
+                niffler::compression::Level::One,
+            )
+            .unwrap();
+            for _ in 0..1000 {
+                writer.write_all(b"@read1\nACGT\n+\nIIII\n").unwrap();
+            }
+        }
+        compressed.truncate(compressed.len() / 2);
+
+        let (prefix, compression) = decompress(&compressed);
+
+        assert_eq!(compression, niffler::compression::Format::Gzip);
+        assert!(prefix.starts_with(b"@read1\nACGT\n"));
+
+        assert_eq!(
+            decompress(b"@1\n"),
+            (b"@1\n".to_vec(), niffler::compression::Format::No)
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! Reference sequences dictionary of alignment files
+
+/* std use */
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/* crates use */
+
+/* project use */
+
+/// Name and length of reference sequences, in order of file header
+pub type Dictionary = Vec<(String, u64)>;
+
+/// Build a Dictionary from reference sequences of a sam header
+pub fn from_header(reference_sequences: &noodles::sam::header::ReferenceSequences) -> Dictionary {
+    reference_sequences
+        .iter()
+        .map(|(name, reference)| (name.to_string(), reference.len() as u64))
+        .collect()
+}
+
+/// Dictionary of each input file, shared between a reader and metrics
+///
+/// Reader insert dictionary of a file when it's open, before any record of this file is read,
+/// metrics can get it with index of file.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionaries(Arc<RwLock<HashMap<usize, Arc<Dictionary>>>>);
+
+impl Dictionaries {
+    /// Create an empty Dictionaries
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set dictionary of file index
+    pub fn insert(&self, index: usize, dictionary: Dictionary) {
+        self.0.write().unwrap().insert(index, Arc::new(dictionary));
+    }
+
+    /// Get dictionary of file index, None if file isn't open
+    pub fn get(&self, index: usize) -> Option<Arc<Dictionary>> {
+        self.0.read().unwrap().get(&index).cloned()
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn from_header_() {
+        let header = noodles::sam::Header::builder()
+            .add_reference_sequence(
+                noodles::sam::header::ReferenceSequence::new("chr1".parse().unwrap(), 100).unwrap(),
+            )
+            .add_reference_sequence(
+                noodles::sam::header::ReferenceSequence::new("chrM".parse().unwrap(), 16).unwrap(),
+            )
+            .build();
+
+        assert_eq!(
+            from_header(header.reference_sequences()),
+            vec![("chr1".to_string(), 100), ("chrM".to_string(), 16)]
+        );
+    }
+
+    #[test]
+    fn dictionaries() {
+        let dictionaries = Dictionaries::new();
+        let other = dictionaries.clone();
+
+        dictionaries.insert(1, vec![("chr1".to_string(), 100)]);
+
+        assert!(other.get(0).is_none());
+        assert_eq!(other.get(1).unwrap()[0], ("chr1".to_string(), 100));
+    }
+}
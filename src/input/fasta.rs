@@ -0,0 +1,261 @@
+//! Parse fasta
+
+/* std use */
+use std::io::BufRead;
+
+/* crates use */
+use anyhow::Context;
+
+/* project use */
+use crate::error;
+use crate::input::bgzf;
+use crate::input::fastq;
+use crate::input::stream;
+
+/// Open a fasta
+fn open(
+    source: stream::Source,
+    buffer_size: usize,
+) -> anyhow::Result<std::io::BufReader<Box<dyn std::io::Read + Send>>> {
+    log::debug!("Open file {}", source.path());
+
+    Ok(std::io::BufReader::with_capacity(
+        buffer_size,
+        bgzf::get_reader(source.open()?)?,
+    ))
+}
+
+/// Read a fasta record, sequence lines are concatenate in sequence
+///
+/// Return number of bytes read, 0 at end of file
+fn read_record<R>(
+    reader: &mut R,
+    sequence: &mut Vec<u8>,
+    line: &mut Vec<u8>,
+) -> std::io::Result<usize>
+where
+    R: BufRead,
+{
+    sequence.clear();
+    line.clear();
+
+    let mut nb_bytes_read = reader.read_until(b'\n', line)?;
+    if nb_bytes_read == 0 {
+        return Ok(0);
+    }
+
+    if !line.starts_with(b">") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "fasta record must start with '>'",
+        ));
+    }
+
+    loop {
+        match reader.fill_buf()?.first() {
+            None | Some(b'>') => break,
+            _ => (),
+        }
+
+        line.clear();
+        nb_bytes_read += reader.read_until(b'\n', line)?;
+
+        let end = line
+            .iter()
+            .rposition(|c| *c != b'\n' && *c != b'\r')
+            .map_or(0, |p| p + 1);
+        sequence.extend_from_slice(&line[..end]);
+    }
+
+    Ok(nb_bytes_read)
+}
+
+/// Struct to parse Fasta file, records are convert in fastq::Record without quality
+pub struct Fasta {
+    buffer_size: usize,
+    sources: Vec<stream::Source>,
+    current_index: usize,
+    sequence: Vec<u8>,
+    line: Vec<u8>,
+    current_input: std::io::BufReader<Box<dyn std::io::Read + Send>>,
+}
+
+impl Fasta {
+    /// Create a Fasta struct, with inputs path and size of read buffer
+    /// If last file of inputs can't be open this function return an anyhow::Error
+    /// Other inputs can't be open is ignored, an error message is put in log
+    ///
+    /// Each record is tagged with the index of its source path in inputs
+    pub fn new<S: Into<stream::Source>>(
+        inputs: Vec<S>,
+        buffer_size: usize,
+    ) -> anyhow::Result<Self> {
+        let mut inputs: Vec<stream::Source> = inputs.into_iter().map(Into::into).collect();
+        let first = inputs
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("inputs is empty"))?;
+        let first_path = first.path().to_string();
+
+        Ok(Self {
+            buffer_size,
+            current_index: inputs.len(),
+            sources: inputs,
+            sequence: Vec::new(),
+            line: Vec::new(),
+            current_input: open(first, buffer_size).with_context(|| {
+                error::Error::FastaOpenError {
+                    path: first_path.clone(),
+                }
+            })?,
+        })
+    }
+}
+
+impl Iterator for Fasta {
+    type Item = (usize, anyhow::Result<fastq::Record>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match read_record(&mut self.current_input, &mut self.sequence, &mut self.line) {
+            Ok(nb_bytes_read) => {
+                if nb_bytes_read == 0 {
+                    if let Some(new_source) = self.sources.pop() {
+                        let new_path = new_source.path().to_string();
+                        self.current_index = self.sources.len();
+                        match open(new_source, self.buffer_size) {
+                            Ok(new_input) => {
+                                self.current_input = new_input;
+                                self.next()
+                            }
+                            Err(e) => Some((
+                                self.current_index,
+                                Err(e.context(error::Error::FastaOpenError {
+                                    path: new_path.clone(),
+                                })),
+                            )),
+                        }
+                    } else {
+                        None // No new file end of iterator
+                    }
+                } else {
+                    Some((
+                        self.current_index,
+                        Ok(fastq::Record::new(self.sequence.clone(), Vec::new())),
+                    ))
+                }
+            }
+            Err(e) => Some((
+                self.current_index,
+                Err(anyhow::Error::new(e).context(error::Error::FastaParsingError)),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    fn create_fasta_file() -> (NamedTempFile, String) {
+        let tmp_file = tempfile::NamedTempFile::new().unwrap();
+
+        {
+            let mut writer = tmp_file.reopen().unwrap();
+            writeln!(writer, ">1\nACTG").unwrap();
+            writeln!(writer, ">2 description\nACTG\r\nACTG\n").unwrap();
+            write!(writer, ">3\nAACACGTGAGTCC\nGCACACCGGACG").unwrap();
+        }
+
+        let path = tmp_file.path().to_str().unwrap().to_string();
+
+        (tmp_file, path)
+    }
+
+    #[test]
+    fn open_fasta_file() {
+        let (tmp_file, path) = create_fasta_file();
+
+        assert!(open(path.as_str().into(), 10).is_ok());
+
+        tmp_file.close().unwrap();
+
+        assert!(open(path.as_str().into(), 10).is_err());
+    }
+
+    #[test]
+    fn create_fasta_parser() {
+        let (tmp_file, path) = create_fasta_file();
+
+        assert!(Fasta::new(vec![path.clone()], 10).is_ok());
+
+        tmp_file.close().unwrap();
+
+        assert!(Fasta::new(vec![path], 10).is_err());
+    }
+
+    #[test]
+    fn iterate_over_fasta() {
+        let (_e, path) = create_fasta_file();
+
+        let mut reader = Fasta::new(vec![path], 10).unwrap();
+        assert_eq!(
+            reader.next().unwrap().1.unwrap(),
+            fastq::Record::new(b"ACTG".to_vec(), Vec::new())
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"ACTGACTG".to_vec()
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"AACACGTGAGTCCGCACACCGGACG".to_vec()
+        );
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn iterate_over_fasta_error() {
+        let tmp_file = tempfile::NamedTempFile::new().unwrap();
+        let path = tmp_file.path().to_str().unwrap().to_string();
+
+        {
+            let mut writer = tmp_file.reopen().unwrap();
+            writeln!(writer, "Failled record\n>1\nACTG").unwrap();
+        }
+
+        let mut reader = Fasta::new(vec![path], 10).unwrap();
+
+        let record = reader.next();
+
+        assert!(record.is_some());
+        assert!(record.unwrap().1.is_err());
+    }
+
+    #[test]
+    fn iterate_over_two_fasta_notfile() {
+        let (_file1, path1) = create_fasta_file();
+        let (_file2, path2) = create_fasta_file();
+        let (file3, path3) = create_fasta_file();
+
+        file3.close().unwrap();
+
+        let reader = Fasta::new(vec![path3, path2, path1], 10).unwrap();
+        let records: Vec<(usize, bool)> = reader.map(|(i, r)| (i, r.is_ok())).collect();
+
+        assert_eq!(
+            records,
+            vec![
+                (2, true),
+                (2, true),
+                (2, true),
+                (1, true),
+                (1, true),
+                (1, true),
+                (0, false)
+            ]
+        );
+    }
+}
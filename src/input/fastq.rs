@@ -7,26 +7,54 @@ use anyhow::Context;
 
 /* project use */
 use crate::error;
+use crate::input::batch;
+use crate::input::bgzf;
+use crate::input::stream;
 
 /// Open a fastq
 fn open(
-    path: &str,
+    source: stream::Source,
     buffer_size: usize,
 ) -> anyhow::Result<noodles::fastq::Reader<std::io::BufReader<Box<dyn std::io::Read + Send>>>> {
-    log::debug!("Open file {}", path);
+    log::debug!("Open file {}", source.path());
 
     Ok(noodles::fastq::Reader::new(
-        std::io::BufReader::with_capacity(
-            buffer_size,
-            niffler::send::get_reader(Box::new(std::fs::File::open(path)?))?.0,
-        ),
+        std::io::BufReader::with_capacity(buffer_size, bgzf::get_reader(source.open()?)?),
     ))
 }
 
+/// Sequence and quality of a fastq record
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Record {
+    sequence: Vec<u8>,
+    quality_scores: Vec<u8>,
+}
+
+impl Record {
+    /// Create a Record from sequence and quality string
+    pub fn new(sequence: Vec<u8>, quality_scores: Vec<u8>) -> Self {
+        Self {
+            sequence,
+            quality_scores,
+        }
+    }
+
+    /// Get sequence
+    pub fn sequence(&self) -> &[u8] {
+        &self.sequence
+    }
+
+    /// Get quality string, encoded in Phred+33
+    pub fn quality_scores(&self) -> &[u8] {
+        &self.quality_scores
+    }
+}
+
 /// Struct to parse Fastq file
 pub struct Fastq {
     buffer_size: usize,
-    paths: Vec<String>,
+    sources: Vec<stream::Source>,
+    current_index: usize,
     local_record: noodles::fastq::Record,
     current_input: noodles::fastq::Reader<std::io::BufReader<Box<dyn std::io::Read + Send>>>,
 }
@@ -35,16 +63,24 @@ impl Fastq {
     /// Create a Fastq struct, with inputs path and size of read buffer
     /// If last file of inputs can't be open this function return an anyhow::Error
     /// Other inputs can't be open is ignored, an error message is put in log
-    pub fn new(mut inputs: Vec<String>, buffer_size: usize) -> anyhow::Result<Self> {
-        let first_path = inputs
+    ///
+    /// Each record is tagged with the index of its source path in inputs
+    pub fn new<S: Into<stream::Source>>(
+        inputs: Vec<S>,
+        buffer_size: usize,
+    ) -> anyhow::Result<Self> {
+        let mut inputs: Vec<stream::Source> = inputs.into_iter().map(Into::into).collect();
+        let first = inputs
             .pop()
             .ok_or_else(|| anyhow::anyhow!("inputs is empty"))?;
+        let first_path = first.path().to_string();
 
         Ok(Self {
             buffer_size,
-            paths: inputs,
+            current_index: inputs.len(),
+            sources: inputs,
             local_record: noodles::fastq::Record::default(),
-            current_input: open(&first_path, buffer_size).with_context(|| {
+            current_input: open(first, buffer_size).with_context(|| {
                 error::Error::FastqOpenError {
                     path: first_path.clone(),
                 }
@@ -53,58 +89,104 @@ impl Fastq {
     }
 }
 
+impl Fastq {
+    /// Open next input, return None if all inputs are read
+    fn next_input(&mut self) -> Option<anyhow::Result<()>> {
+        let new_source = self.sources.pop()?;
+        let new_path = new_source.path().to_string();
+        self.current_index = self.sources.len();
+
+        match open(new_source, self.buffer_size) {
+            Ok(new_input) => {
+                self.current_input = new_input;
+                Some(Ok(()))
+            }
+            Err(e) => Some(Err(e.context(error::Error::FastqOpenError {
+                path: new_path.clone(),
+            }))),
+        }
+    }
+}
+
 impl Iterator for Fastq {
-    type Item = anyhow::Result<Vec<u8>>;
+    type Item = (usize, anyhow::Result<Record>);
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.current_input.read_record(&mut self.local_record) {
             Ok(nb_bytes_read) => {
                 if nb_bytes_read == 0 {
-                    if let Some(new_path) = self.paths.pop() {
-                        match open(&new_path, self.buffer_size) {
-                            Ok(new_input) => {
-                                self.current_input = new_input;
-                                self.next()
-                            }
-                            Err(e) => Some(Err(e.context(error::Error::FastqOpenError {
-                                path: new_path.clone(),
-                            }))),
-                        }
-                    } else {
-                        None // No new file end of iterator
+                    match self.next_input()? {
+                        Ok(()) => self.next(),
+                        Err(e) => Some((self.current_index, Err(e))),
                     }
                 } else {
-                    Some(Ok(self.local_record.sequence().to_vec()))
+                    Some((
+                        self.current_index,
+                        Ok(Record::new(
+                            self.local_record.sequence().to_vec(),
+                            self.local_record.quality_scores().to_vec(),
+                        )),
+                    ))
                 }
             }
-            Err(e) => Some(Err(
-                anyhow::Error::new(e).context(error::Error::FastqParsingError)
+            Err(e) => Some((
+                self.current_index,
+                Err(anyhow::Error::new(e).context(error::Error::FastqParsingError)),
             )),
         }
     }
 }
 
-pub fn worker(wrapper: anyhow::Result<Vec<u8>>) -> (u64, u64, u64, u64) {
-    match wrapper {
-        Ok(seq) => {
-            let mut at: u64 = 0;
-            let mut gc: u64 = 0;
-            let mut other: u64 = 0;
-
-            for n in seq {
-                match n {
-                    b'a' | b'A' | b't' | b'T' => at += 1,
-                    b'c' | b'C' | b'g' | b'G' => gc += 1,
-                    _ => other += 1,
+impl batch::Reader for Fastq {
+    type Record = Record;
+
+    fn fill(&mut self, batch: &mut batch::Batch<Record>) -> bool {
+        batch.clear(self.current_index);
+
+        while !batch.is_full() {
+            match self.current_input.read_record(&mut self.local_record) {
+                Ok(0) => {
+                    if !batch.is_empty() {
+                        return true;
+                    }
+
+                    match self.next_input() {
+                        Some(Ok(())) => batch.clear(self.current_index),
+                        Some(Err(e)) => {
+                            batch.clear(self.current_index);
+                            batch.push_error(e);
+                            return true;
+                        }
+                        None => return false,
+                    }
+                }
+                Ok(nb_bytes_read) => {
+                    let record = batch.next_slot();
+                    record.sequence.clear();
+                    record
+                        .sequence
+                        .extend_from_slice(self.local_record.sequence());
+                    record.quality_scores.clear();
+                    record
+                        .quality_scores
+                        .extend_from_slice(self.local_record.quality_scores());
+
+                    batch.add_bytes(nb_bytes_read);
+                }
+                Err(e) => {
+                    batch
+                        .push_error(anyhow::Error::new(e).context(error::Error::FastqParsingError));
+
+                    // input can't be read after an error, rest of input is skip
+                    self.current_input = noodles::fastq::Reader::new(std::io::BufReader::new(
+                        Box::new(std::io::empty()),
+                    ));
+                    return true;
                 }
             }
-
-            (at, gc, other, 1)
-        }
-        Err(e) => {
-            e.chain().for_each(|b| log::error!("{}", b));
-            (0, 0, 0, 0)
         }
+
+        true
     }
 }
 
@@ -139,11 +221,11 @@ mod t {
     fn open_fastq_file() {
         let (tmp_file, path) = create_fastq_file();
 
-        assert!(open(&path, 10).is_ok());
+        assert!(open(path.as_str().into(), 10).is_ok());
 
         tmp_file.close().unwrap();
 
-        assert!(open(&path, 10).is_err());
+        assert!(open(path.as_str().into(), 10).is_err());
     }
 
     #[test]
@@ -162,11 +244,20 @@ mod t {
         let (_e, path) = create_fastq_file();
 
         let mut reader = Fastq::new(vec![path], 10).unwrap();
-        assert_eq!(reader.next().unwrap().unwrap(), b"ACTG".to_vec());
-        assert_eq!(reader.next().unwrap().unwrap(), b"ACTGACTG".to_vec());
         assert_eq!(
-            reader.next().unwrap().unwrap(),
-            b"AACACGTGAGTCCGCACACCGGACG".to_vec()
+            reader.next().unwrap().1.unwrap(),
+            Record::new(b"ACTG".to_vec(), b"!!!!".to_vec())
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"ACTGACTG".to_vec()
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap(),
+            Record::new(
+                b"AACACGTGAGTCCGCACACCGGACG".to_vec(),
+                b"`kL7sm$xKvE8.RT`[kgO!34O'".to_vec()
+            )
         );
     }
 
@@ -178,17 +269,23 @@ mod t {
         writeln!(file, "Failled record").unwrap();
 
         let mut reader = Fastq::new(vec![path], 10).unwrap();
-        assert_eq!(reader.next().unwrap().unwrap(), b"ACTG".to_vec());
-        assert_eq!(reader.next().unwrap().unwrap(), b"ACTGACTG".to_vec());
         assert_eq!(
-            reader.next().unwrap().unwrap(),
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"ACTG".to_vec()
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"ACTGACTG".to_vec()
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
             b"AACACGTGAGTCCGCACACCGGACG".to_vec()
         );
 
         let record = reader.next();
 
         assert!(record.is_some());
-        assert!(record.unwrap().is_err());
+        assert!(record.unwrap().1.is_err());
     }
 
     #[test]
@@ -197,16 +294,28 @@ mod t {
         let (_file2, path2) = create_fastq_file();
 
         let mut reader = Fastq::new(vec![path2, path1], 10).unwrap();
-        assert_eq!(reader.next().unwrap().unwrap(), b"ACTG".to_vec());
-        assert_eq!(reader.next().unwrap().unwrap(), b"ACTGACTG".to_vec());
         assert_eq!(
-            reader.next().unwrap().unwrap(),
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"ACTG".to_vec()
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"ACTGACTG".to_vec()
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
             b"AACACGTGAGTCCGCACACCGGACG".to_vec()
         );
-        assert_eq!(reader.next().unwrap().unwrap(), b"ACTG".to_vec());
-        assert_eq!(reader.next().unwrap().unwrap(), b"ACTGACTG".to_vec());
         assert_eq!(
-            reader.next().unwrap().unwrap(),
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"ACTG".to_vec()
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"ACTGACTG".to_vec()
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
             b"AACACGTGAGTCCGCACACCGGACG".to_vec()
         );
         assert!(reader.next().is_none());
@@ -221,28 +330,125 @@ mod t {
         file3.close().unwrap();
 
         let mut reader = Fastq::new(vec![path3, path2, path1], 10).unwrap();
-        assert_eq!(reader.next().unwrap().unwrap(), b"ACTG".to_vec());
-        assert_eq!(reader.next().unwrap().unwrap(), b"ACTGACTG".to_vec());
         assert_eq!(
-            reader.next().unwrap().unwrap(),
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"ACTG".to_vec()
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"ACTGACTG".to_vec()
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
             b"AACACGTGAGTCCGCACACCGGACG".to_vec()
         );
-        assert_eq!(reader.next().unwrap().unwrap(), b"ACTG".to_vec());
-        assert_eq!(reader.next().unwrap().unwrap(), b"ACTGACTG".to_vec());
         assert_eq!(
-            reader.next().unwrap().unwrap(),
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"ACTG".to_vec()
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
+            b"ACTGACTG".to_vec()
+        );
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence(),
             b"AACACGTGAGTCCGCACACCGGACG".to_vec()
         );
 
         let failled_record = reader.next();
         assert!(failled_record.is_some());
-        assert!(failled_record.unwrap().is_err());
+
+        let (index, record) = failled_record.unwrap();
+        assert_eq!(index, 0);
+        assert!(record.is_err());
+    }
+
+    #[test]
+    fn iterate_over_two_fastq_index() {
+        let (_file1, path1) = create_fastq_file();
+        let (_file2, path2) = create_fastq_file();
+
+        let reader = Fastq::new(vec![path2, path1], 10).unwrap();
+        let indexes: Vec<usize> = reader.map(|(index, _)| index).collect();
+
+        assert_eq!(indexes, vec![1, 1, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn fill_batch() {
+        let (_file1, path1) = create_fastq_file();
+        let (_file2, path2) = create_fastq_file();
+        let (file3, path3) = create_fastq_file();
+
+        file3.close().unwrap();
+
+        let mut reader = Fastq::new(vec![path3, path2, path1], 10).unwrap();
+        let mut batch = batch::Batch::new(20);
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.index(), 2);
+        assert_eq!(
+            batch.records(),
+            &[
+                Record::new(b"ACTG".to_vec(), b"!!!!".to_vec()),
+                Record::new(b"ACTGACTG".to_vec(), b"!!!!!!!!".to_vec())
+            ]
+        );
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.index(), 2);
+        assert_eq!(batch.records().len(), 1);
+        assert_eq!(batch.records()[0].sequence(), b"AACACGTGAGTCCGCACACCGGACG");
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.index(), 1);
+        assert_eq!(batch.records().len(), 2);
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.index(), 1);
+        assert_eq!(batch.records().len(), 1);
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.index(), 0);
+        assert!(batch.records().is_empty());
+        assert_eq!(batch.take_errors().len(), 1);
+
+        assert!(!batch::Reader::fill(&mut reader, &mut batch));
+    }
+
+    struct Failing;
+
+    impl std::io::Read for Failing {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "read failled",
+            ))
+        }
     }
 
     #[test]
-    fn run_worker() {
-        assert_eq!(worker(Ok(b"ACTGN".to_vec())), (2, 2, 1, 1));
+    fn fill_batch_read_error() {
+        let (_file1, path1) = create_fastq_file();
+        let (_file2, path2) = create_fastq_file();
 
-        assert_eq!(worker(Err(anyhow::anyhow!("prout"))), (0, 0, 0, 0));
+        let mut reader = Fastq::new(vec![path2, path1], 10).unwrap();
+        reader.current_input =
+            noodles::fastq::Reader::new(std::io::BufReader::new(Box::new(Failing)));
+        let mut batch = batch::Batch::new(20);
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.index(), 1);
+        assert!(batch.records().is_empty());
+        assert_eq!(batch.take_errors().len(), 1);
+
+        // rest of failling input is skip, next input is read
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert_eq!(batch.index(), 0);
+        assert_eq!(batch.records().len(), 2);
+        assert!(batch.take_errors().is_empty());
+
+        assert!(batch::Reader::fill(&mut reader, &mut batch));
+        assert!(!batch::Reader::fill(&mut reader, &mut batch));
     }
 }
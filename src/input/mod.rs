@@ -2,8 +2,25 @@
 
 /* mod declaration section */
 pub mod bam;
+pub mod batch;
+pub mod bed;
+pub mod bgzf;
+pub mod coverage;
+pub mod cram;
+pub mod detect;
+pub mod dictionary;
+pub mod fasta;
 pub mod fastq;
+pub mod region;
+pub mod sam;
+pub mod stream;
 
 /* pub use section */
 pub use bam::Bam;
+pub use coverage::Depths;
+pub use cram::Cram;
+pub use detect::Format;
+pub use dictionary::Dictionaries;
+pub use fasta::Fasta;
 pub use fastq::Fastq;
+pub use sam::Sam;
@@ -0,0 +1,198 @@
+//! Parse regions and find bgzf blocks of regions with bam index
+
+/* std use */
+
+/* crates use */
+use anyhow::Context;
+use noodles::csi::BinningIndex;
+
+/* project use */
+use crate::error;
+use crate::input::bed;
+use crate::input::dictionary;
+
+/// A part of bam file, delimited by virtual positions
+pub type Chunk = noodles::csi::index::reference_sequence::bin::Chunk;
+
+/// A region resolved on a dictionary, reference sequence id, start and end (1-based, include)
+pub type Interval = (usize, i32, i32);
+
+/// Parse a region in samtools format (chr1, chr1:1000 or chr1:1-1000000), coordinates are
+/// 1-based, region is return as a [bed::Target] so coordinates are convert to 0-based
+pub fn parse(region: &str) -> anyhow::Result<bed::Target> {
+    let parse = || -> anyhow::Result<bed::Target> {
+        let parsed: noodles::core::Region = region.parse()?;
+        let mapped = parsed
+            .as_mapped()
+            .ok_or_else(|| anyhow::anyhow!("region must be on a reference sequence"))?;
+
+        let start = match mapped.start() {
+            std::ops::Bound::Included(start) if start > 0 => start as u64 - 1,
+            std::ops::Bound::Unbounded => 0,
+            _ => anyhow::bail!("start of region must be greater than 0"),
+        };
+        let end = match mapped.end() {
+            std::ops::Bound::Included(end) => end as u64,
+            _ => u64::MAX,
+        };
+        if end < start {
+            anyhow::bail!("end of region is before start");
+        }
+
+        Ok(bed::Target {
+            chrom: mapped.name().to_string(),
+            start,
+            end,
+            name: region.to_string(),
+        })
+    };
+
+    parse().with_context(|| error::Error::RegionParsingError {
+        region: region.to_string(),
+    })
+}
+
+/// Convert regions in intervals of a file, end of region is clamp to reference sequence length
+///
+/// Regions on reference sequence absent of dictionary are ignored, a warning is put in log
+pub fn resolve(regions: &[bed::Target], dictionary: &dictionary::Dictionary) -> Vec<Interval> {
+    regions
+        .iter()
+        .filter_map(|region| {
+            let id = dictionary
+                .iter()
+                .position(|(name, _)| name == &region.chrom);
+            if id.is_none() {
+                log::warn!(
+                    "Reference sequence {} of region {} isn't in file",
+                    region.chrom,
+                    region.name
+                );
+            }
+            let id = id?;
+
+            let end = region.end.min(dictionary[id].1).min(i32::MAX as u64) as i32;
+            let start = region.start.min(i32::MAX as u64 - 1) as i32 + 1;
+            if start > end {
+                return None;
+            }
+
+            Some((id, start, end))
+        })
+        .collect()
+}
+
+/// Find index of a bam, `path.bai`, `path` with `.bam` replace by `.bai` or `path.csi`
+pub fn index_path(path: &str) -> Option<String> {
+    let mut candidates = vec![format!("{}.bai", path)];
+    if let Some(prefix) = path.strip_suffix(".bam") {
+        candidates.push(format!("{}.bai", prefix));
+    }
+    candidates.push(format!("{}.csi", path));
+
+    candidates
+        .into_iter()
+        .find(|candidate| std::path::Path::new(candidate).is_file())
+}
+
+/// Chunks of bam path overlapping intervals, sorted and merged, index is found with [index_path]
+pub fn chunks(path: &str, intervals: &[Interval]) -> anyhow::Result<Vec<Chunk>> {
+    let index = index_path(path).ok_or_else(|| error::Error::BamIndexNotFound {
+        path: path.to_string(),
+    })?;
+    log::debug!("Read index {}", index);
+
+    if index.ends_with(".csi") {
+        query(&noodles::csi::read(&index)?, intervals)
+    } else {
+        query(&noodles::bam::bai::read(&index)?, intervals)
+    }
+}
+
+/// Merged chunks of index overlapping intervals
+fn query<I, RS>(index: &I, intervals: &[Interval]) -> anyhow::Result<Vec<Chunk>>
+where
+    I: BinningIndex<RS>,
+    RS: noodles::csi::BinningIndexReferenceSequence,
+{
+    let mut chunks = Vec::new();
+    for (id, start, end) in intervals {
+        chunks.extend(index.query(*id, *start..=*end)?);
+    }
+
+    Ok(noodles::csi::binning_index::merge_chunks(&chunks))
+}
+
+/// Return true if alignment of record overlap one of intervals
+pub fn overlaps(intervals: &[Interval], record: &noodles::bam::Record) -> bool {
+    let (id, start) = match (record.reference_sequence_id(), record.position()) {
+        (Some(id), Some(position)) => (i32::from(id) as usize, i32::from(position)),
+        _ => return false,
+    };
+    let end = start + record.cigar().reference_len().unwrap_or(0).max(1) as i32 - 1;
+
+    intervals
+        .iter()
+        .any(|(i, s, e)| *i == id && start <= *e && end >= *s)
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn parse_() {
+        let region = parse("chr1:1-1000000").unwrap();
+        assert_eq!(region.chrom, "chr1");
+        assert_eq!(region.start, 0);
+        assert_eq!(region.end, 1000000);
+        assert_eq!(region.name, "chr1:1-1000000");
+
+        let region = parse("chrM").unwrap();
+        assert_eq!(region.start, 0);
+        assert_eq!(region.end, u64::MAX);
+
+        assert!(parse("").is_err());
+        assert!(parse("*").is_err());
+        assert!(parse("chr1:ten-20").is_err());
+        assert!(parse("chr1:20-10").is_err());
+        assert!(parse("chr1:0-10").is_err());
+    }
+
+    #[test]
+    fn resolve_() {
+        let dictionary = vec![("chr1".to_string(), 100), ("chr2".to_string(), 50)];
+        let regions = vec![
+            parse("chr2").unwrap(),
+            parse("chr1:10-20").unwrap(),
+            parse("chrM:1-10").unwrap(),
+            parse("chr2:60-70").unwrap(),
+        ];
+
+        assert_eq!(
+            resolve(&regions, &dictionary),
+            vec![(1, 1, 50), (0, 10, 20)]
+        );
+    }
+
+    #[test]
+    fn index_path_() {
+        let directory = tempfile::tempdir().unwrap();
+        let bam = directory.path().join("reads.bam");
+        let bam = bam.to_str().unwrap();
+
+        assert_eq!(index_path(bam), None);
+
+        std::fs::File::create(format!("{}.csi", bam)).unwrap();
+        assert_eq!(index_path(bam), Some(format!("{}.csi", bam)));
+
+        let bai = directory.path().join("reads.bai");
+        std::fs::File::create(&bai).unwrap();
+        assert_eq!(index_path(bam), Some(bai.to_str().unwrap().to_string()));
+
+        std::fs::File::create(format!("{}.bai", bam)).unwrap();
+        assert_eq!(index_path(bam), Some(format!("{}.bai", bam)));
+
+        assert!(chunks(bam, &[]).is_err());
+    }
+}
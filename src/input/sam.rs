@@ -0,0 +1,264 @@
+//! Parse sam
+
+/* std use */
+
+/* crates use */
+use anyhow::Context;
+
+/* project use */
+use crate::error;
+use crate::input::bgzf;
+use crate::input::dictionary;
+use crate::input::stream;
+
+/// Sam reader and header of file
+type Input = (
+    noodles::sam::Reader<std::io::BufReader<Box<dyn std::io::Read + Send>>>,
+    noodles::sam::Header,
+);
+
+/// Open a sam
+fn open(source: stream::Source, buffer_size: usize) -> anyhow::Result<Input> {
+    log::debug!("Open file {}", source.path());
+
+    let mut reader = noodles::sam::Reader::new(std::io::BufReader::with_capacity(
+        buffer_size,
+        bgzf::get_reader(source.open()?)?,
+    ));
+    let header = reader.read_header()?.parse()?;
+
+    Ok((reader, header))
+}
+
+/// Struct to parse Sam file, records are convert in bam record
+pub struct Sam {
+    buffer_size: usize,
+    sources: Vec<stream::Source>,
+    current_index: usize,
+    line: String,
+    current_input: Input,
+    dictionaries: dictionary::Dictionaries,
+}
+
+impl Sam {
+    /// Create a Sam struct, with inputs path and size of read buffer
+    /// If last file of inputs can't be open this function return an anyhow::Error
+    /// Other inputs can't be open is ignored, an error message is put in log
+    ///
+    /// Each record is tagged with the index of its source path in inputs
+    pub fn new<S: Into<stream::Source>>(
+        inputs: Vec<S>,
+        buffer_size: usize,
+    ) -> anyhow::Result<Self> {
+        let mut inputs: Vec<stream::Source> = inputs.into_iter().map(Into::into).collect();
+        let first = inputs
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("inputs is empty"))?;
+        let first_path = first.path().to_string();
+
+        let current_input =
+            open(first, buffer_size).with_context(|| error::Error::SamOpenError {
+                path: first_path.clone(),
+            })?;
+
+        let dictionaries = dictionary::Dictionaries::new();
+        dictionaries.insert(
+            inputs.len(),
+            dictionary::from_header(current_input.1.reference_sequences()),
+        );
+
+        Ok(Self {
+            buffer_size,
+            current_index: inputs.len(),
+            sources: inputs,
+            line: String::new(),
+            current_input,
+            dictionaries,
+        })
+    }
+
+    /// Reference sequences dictionary of each file, a file dictionary is set when file is open
+    pub fn dictionaries(&self) -> dictionary::Dictionaries {
+        self.dictionaries.clone()
+    }
+
+    /// Parse line as a sam record and convert it in bam record
+    fn convert(&self) -> anyhow::Result<noodles::bam::Record> {
+        let record: noodles::sam::Record = self.line.parse()?;
+
+        Ok(noodles::bam::Record::try_from_sam_record(
+            self.current_input.1.reference_sequences(),
+            &record,
+        )?)
+    }
+}
+
+impl Iterator for Sam {
+    type Item = (usize, anyhow::Result<noodles::bam::Record>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.line.clear();
+
+        match self.current_input.0.read_record(&mut self.line) {
+            Ok(nb_bytes_read) => {
+                if nb_bytes_read == 0 {
+                    if let Some(new_source) = self.sources.pop() {
+                        let new_path = new_source.path().to_string();
+                        self.current_index = self.sources.len();
+                        match open(new_source, self.buffer_size) {
+                            Ok(new_input) => {
+                                self.dictionaries.insert(
+                                    self.current_index,
+                                    dictionary::from_header(new_input.1.reference_sequences()),
+                                );
+                                self.current_input = new_input;
+                                self.next()
+                            }
+                            Err(e) => Some((
+                                self.current_index,
+                                Err(e.context(error::Error::SamOpenError {
+                                    path: new_path.clone(),
+                                })),
+                            )),
+                        }
+                    } else {
+                        None // No new file end of iterator
+                    }
+                } else {
+                    Some((
+                        self.current_index,
+                        self.convert().context(error::Error::SamParsingError),
+                    ))
+                }
+            }
+            Err(e) => Some((
+                self.current_index,
+                Err(anyhow::Error::new(e).context(error::Error::SamParsingError)),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use std::io::{Seek, Write};
+
+    use tempfile::NamedTempFile;
+
+    fn create_sam_file() -> (NamedTempFile, String) {
+        let tmp_file = tempfile::NamedTempFile::new().unwrap();
+
+        {
+            let mut writer = tmp_file.reopen().unwrap();
+            writeln!(writer, "@HD\tVN:1.6\tSO:coordinate").unwrap();
+            writeln!(writer, "@SQ\tSN:chr1\tLN:100").unwrap();
+            writeln!(writer, "1\t4\t*\t0\t255\t*\t*\t0\t0\tACTG\t!!!!").unwrap();
+            writeln!(writer, "2\t0\tchr1\t1\t60\t8M\t*\t0\t0\tACTGACTG\t*").unwrap();
+            writeln!(
+                writer,
+                "3\t4\t*\t0\t255\t*\t*\t0\t0\tAACACGTGAGTCCGCACACCGGACG\t*"
+            )
+            .unwrap();
+        }
+
+        let path = tmp_file.path().to_str().unwrap().to_string();
+
+        (tmp_file, path)
+    }
+
+    #[test]
+    fn open_sam_file() {
+        let (tmp_file, path) = create_sam_file();
+
+        assert!(open(path.as_str().into(), 10).is_ok());
+
+        tmp_file.close().unwrap();
+
+        assert!(open(path.as_str().into(), 10).is_err());
+    }
+
+    #[test]
+    fn create_sam_parser() {
+        let (tmp_file, path) = create_sam_file();
+
+        assert!(Sam::new(vec![path.clone()], 10).is_ok());
+
+        tmp_file.close().unwrap();
+
+        assert!(Sam::new(vec![path], 10).is_err());
+    }
+
+    #[test]
+    fn iterate_over_sam() {
+        let (_e, path) = create_sam_file();
+
+        let mut reader = Sam::new(vec![path], 10).unwrap();
+
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
+            [18, 132]
+        );
+
+        let mapped = reader.next().unwrap().1.unwrap();
+        assert_eq!(mapped.sequence().as_ref()[..], [18, 132, 18, 132]);
+        assert_eq!(mapped.reference_sequence_id().map(i32::from), Some(0));
+
+        assert_eq!(
+            reader.next().unwrap().1.unwrap().sequence().as_ref()[..],
+            [17, 33, 36, 132, 20, 130, 36, 33, 33, 34, 68, 18, 64]
+        );
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn dictionaries() {
+        let (_e, path) = create_sam_file();
+
+        let reader = Sam::new(vec![path], 10).unwrap();
+
+        assert_eq!(
+            reader.dictionaries().get(0).unwrap()[..],
+            [("chr1".to_string(), 100)]
+        );
+    }
+
+    #[test]
+    fn iterate_over_sam_error() {
+        let (mut file, path) = create_sam_file();
+
+        file.seek(std::io::SeekFrom::End(0)).unwrap();
+        writeln!(file, "Failled record").unwrap();
+
+        let reader = Sam::new(vec![path], 10).unwrap();
+        let records: Vec<bool> = reader.map(|(_, r)| r.is_ok()).collect();
+
+        assert_eq!(records, vec![true, true, true, false]);
+    }
+
+    #[test]
+    fn iterate_over_two_sam_notfile() {
+        let (_file1, path1) = create_sam_file();
+        let (_file2, path2) = create_sam_file();
+        let (file3, path3) = create_sam_file();
+
+        file3.close().unwrap();
+
+        let reader = Sam::new(vec![path3, path2, path1], 10).unwrap();
+        let records: Vec<(usize, bool)> = reader.map(|(i, r)| (i, r.is_ok())).collect();
+
+        assert_eq!(
+            records,
+            vec![
+                (2, true),
+                (2, true),
+                (2, true),
+                (1, true),
+                (1, true),
+                (1, true),
+                (0, false)
+            ]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Open input stream, path `-` is stdin
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+
+/// Path use to read stdin
+pub const STDIN: &str = "-";
+
+/// Raw input stream
+pub type Stream = Box<dyn std::io::Read + Send>;
+
+/// Return true if path can't be reopen: stdin, named pipe, ...
+pub fn is_stream(path: &str) -> bool {
+    path == STDIN
+        || !std::fs::metadata(path)
+            .map(|meta| meta.is_file())
+            .unwrap_or(true)
+}
+
+/// Open path, `-` is stdin
+pub fn open(path: &str) -> std::io::Result<Stream> {
+    if path == STDIN {
+        Ok(Box::new(std::io::stdin()))
+    } else {
+        Ok(Box::new(std::fs::File::open(path)?))
+    }
+}
+
+/// An input, a path not yet open or a stream already open (partially consume by format
+/// detection, tee, ...) with its path
+pub enum Source {
+    Path(String),
+    Open(String, Stream),
+}
+
+impl Source {
+    /// Path of input
+    pub fn path(&self) -> &str {
+        match self {
+            Source::Path(path) => path,
+            Source::Open(path, _) => path,
+        }
+    }
+
+    /// Get stream of input, path is open if it isn't already
+    pub fn open(self) -> std::io::Result<Stream> {
+        match self {
+            Source::Path(path) => open(&path),
+            Source::Open(_, stream) => Ok(stream),
+        }
+    }
+
+    /// Stream of source copy all bytes read in copy, see [Tee]
+    pub fn tee(&mut self, copy: Box<dyn std::io::Write + Send>) -> std::io::Result<()> {
+        let path = self.path().to_string();
+        let inner = std::mem::replace(self, Source::Path(path.clone())).open()?;
+
+        *self = Source::Open(path, Box::new(Tee::new(inner, copy)));
+
+        Ok(())
+    }
+}
+
+impl From<String> for Source {
+    fn from(path: String) -> Self {
+        Source::Path(path)
+    }
+}
+
+impl From<&str> for Source {
+    fn from(path: &str) -> Self {
+        Source::Path(path.to_string())
+    }
+}
+
+/// Stream that copy all bytes read in a writer
+///
+/// When Tee is drop, unread bytes of stream are copy, so writer get exactly input content
+pub struct Tee {
+    inner: Stream,
+    copy: Box<dyn std::io::Write + Send>,
+}
+
+impl Tee {
+    /// Create a Tee that copy bytes read from inner in copy
+    pub fn new(inner: Stream, copy: Box<dyn std::io::Write + Send>) -> Self {
+        Self { inner, copy }
+    }
+}
+
+impl std::io::Read for Tee {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let nb_bytes = self.inner.read(buf)?;
+        self.copy.write_all(&buf[..nb_bytes])?;
+
+        Ok(nb_bytes)
+    }
+}
+
+impl Drop for Tee {
+    fn drop(&mut self) {
+        if let Err(e) =
+            std::io::copy(&mut self.inner, &mut self.copy).and_then(|_| self.copy.flush())
+        {
+            log::error!("Error durring tee copy {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use std::io::{Read, Write};
+
+    #[test]
+    fn is_stream_() {
+        let tmp_file = tempfile::NamedTempFile::new().unwrap();
+
+        assert!(is_stream("-"));
+        assert!(!is_stream(tmp_file.path().to_str().unwrap()));
+        assert!(!is_stream("not_exist.fastq"));
+        assert!(is_stream(
+            tmp_file.path().parent().unwrap().to_str().unwrap()
+        ));
+    }
+
+    #[test]
+    fn source() {
+        let mut tmp_file = tempfile::NamedTempFile::new().unwrap();
+        write!(tmp_file, "@1\nACGT\n+\nIIII\n").unwrap();
+        let path = tmp_file.path().to_str().unwrap();
+
+        let source = Source::from(path);
+        assert_eq!(source.path(), path);
+        let mut stream = source.open().unwrap();
+        let mut prefix = vec![0; 5];
+        stream.read_exact(&mut prefix).unwrap();
+
+        let source = Source::Open(
+            path.to_string(),
+            Box::new(std::io::Cursor::new(prefix).chain(stream)),
+        );
+        assert_eq!(source.path(), path);
+
+        // same path open in parallel isn't affected by partially consume stream
+        let mut content = String::new();
+        Source::from(path)
+            .open()
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "@1\nACGT\n+\nIIII\n");
+
+        content.clear();
+        source.open().unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "@1\nACGT\n+\nIIII\n");
+
+        assert!(Source::from("not_exist.fastq").open().is_err());
+    }
+
+    #[test]
+    fn tee_() {
+        let mut tmp_file = tempfile::NamedTempFile::new().unwrap();
+        write!(tmp_file, "@1\nACGT\n+\nIIII\n").unwrap();
+        let path = tmp_file.path().to_str().unwrap();
+
+        let copy_file = tempfile::NamedTempFile::new().unwrap();
+
+        let mut source = Source::from(path);
+        source.tee(Box::new(copy_file.reopen().unwrap())).unwrap();
+        assert_eq!(source.path(), path);
+
+        {
+            let mut stream = source.open().unwrap();
+            let mut prefix = vec![0; 5];
+            stream.read_exact(&mut prefix).unwrap();
+            assert_eq!(prefix, b"@1\nAC");
+        }
+
+        let mut content = String::new();
+        copy_file
+            .reopen()
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "@1\nACGT\n+\nIIII\n");
+
+        assert!(Source::from("not_exist.fastq")
+            .tee(Box::new(std::io::sink()))
+            .is_err());
+    }
+}
@@ -4,5 +4,9 @@
 
 /* mod declaration block */
 pub mod cli;
+pub mod count;
 pub mod error;
 pub mod input;
+pub mod metric;
+pub mod output;
+pub mod report;
@@ -0,0 +1,185 @@
+//! Nucleotide composition of records
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+use crate::count;
+use crate::input::fastq;
+use crate::metric::{Field, Metric, Value};
+
+/// Count A, C, G, T, N, IUPAC and invalid base
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Composition {
+    counts: count::Counts,
+}
+
+impl Composition {
+    /// Create an empty Composition
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn merge_state(&mut self, other: &dyn std::any::Any) {
+        let other = other
+            .downcast_ref::<Self>()
+            .expect("Composition can only be merged with Composition");
+
+        self.counts += other.counts;
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let total = self.counts.total() as f64;
+
+        vec![
+            (
+                "at".to_string(),
+                Value::Float((self.counts.a + self.counts.t) as f64 / total * 100.0),
+            ),
+            (
+                "gc".to_string(),
+                Value::Float(self.counts.gc() as f64 / total * 100.0),
+            ),
+            (
+                "other".to_string(),
+                Value::Float(self.counts.other() as f64 / total * 100.0),
+            ),
+            ("base_a".to_string(), Value::Integer(self.counts.a)),
+            ("base_c".to_string(), Value::Integer(self.counts.c)),
+            ("base_g".to_string(), Value::Integer(self.counts.g)),
+            ("base_t".to_string(), Value::Integer(self.counts.t)),
+            ("base_n".to_string(), Value::Integer(self.counts.n)),
+            ("base_iupac".to_string(), Value::Integer(self.counts.iupac)),
+            (
+                "base_invalid".to_string(),
+                Value::Integer(self.counts.invalid),
+            ),
+        ]
+    }
+}
+
+impl Metric<fastq::Record> for Composition {
+    fn empty(&self) -> Box<dyn Metric<fastq::Record>> {
+        Box::new(Self::new())
+    }
+
+    fn observe(&mut self, record: &fastq::Record) {
+        self.counts += count::count(record.sequence());
+    }
+
+    fn merge(&mut self, other: &dyn Metric<fastq::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl Metric<noodles::bam::Record> for Composition {
+    fn empty(&self) -> Box<dyn Metric<noodles::bam::Record>> {
+        Box::new(Self::new())
+    }
+
+    fn observe(&mut self, record: &noodles::bam::Record) {
+        for n in record.sequence().bases() {
+            match n {
+                noodles::bam::record::sequence::Base::A => self.counts.a += 1,
+                noodles::bam::record::sequence::Base::C => self.counts.c += 1,
+                noodles::bam::record::sequence::Base::G => self.counts.g += 1,
+                noodles::bam::record::sequence::Base::T => self.counts.t += 1,
+                noodles::bam::record::sequence::Base::N => self.counts.n += 1,
+                // '=' isn't a nucleotide
+                noodles::bam::record::sequence::Base::Eq => self.counts.invalid += 1,
+                _ => self.counts.iupac += 1,
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &dyn Metric<noodles::bam::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn fastq() {
+        let mut metric = Composition::new();
+
+        Metric::<fastq::Record>::observe(
+            &mut metric,
+            &fastq::Record::new(b"ACTGNRY-AA".to_vec(), b"!!!!!!!!!!".to_vec()),
+        );
+
+        assert_eq!(
+            Metric::<fastq::Record>::finalize(&metric),
+            vec![
+                ("at".to_string(), Value::Float(40.0)),
+                ("gc".to_string(), Value::Float(20.0)),
+                ("other".to_string(), Value::Float(40.0)),
+                ("base_a".to_string(), Value::Integer(3)),
+                ("base_c".to_string(), Value::Integer(1)),
+                ("base_g".to_string(), Value::Integer(1)),
+                ("base_t".to_string(), Value::Integer(1)),
+                ("base_n".to_string(), Value::Integer(1)),
+                ("base_iupac".to_string(), Value::Integer(2)),
+                ("base_invalid".to_string(), Value::Integer(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn bam() {
+        let record = noodles::bam::Record::try_from_sam_record(
+            noodles::sam::Header::builder()
+                .build()
+                .reference_sequences(),
+            &noodles::sam::Record::builder()
+                .set_read_name("1".parse().unwrap())
+                .set_flags(noodles::sam::record::Flags::UNMAPPED)
+                .set_sequence("ACTGNRA".parse().unwrap())
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let mut metric = Composition::new();
+        Metric::<noodles::bam::Record>::observe(&mut metric, &record);
+
+        let mut other = Composition::new();
+        Metric::<noodles::bam::Record>::observe(&mut other, &record);
+
+        Metric::<noodles::bam::Record>::merge(&mut metric, &other);
+
+        assert_eq!(
+            metric,
+            Composition {
+                counts: count::Counts {
+                    a: 4,
+                    c: 2,
+                    g: 2,
+                    t: 2,
+                    n: 2,
+                    iupac: 2,
+                    invalid: 0
+                }
+            }
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! Depth of coverage of alignment files
+//!
+//! Depth need records in file order, it's compute by reader (see [crate::input::coverage]),
+//! this metric only collect depth of files it observe.
+
+/* std use */
+use std::collections::BTreeSet;
+
+/* crates use */
+
+/* project use */
+use crate::input::coverage;
+use crate::metric::{Field, Metric, Value};
+
+/// Percentile of target depth use to compute fold-80 base penalty
+const FOLD_80_PERCENTILE: f64 = 0.2;
+
+/// Mean depth, breadth of coverage and depth histogram, by file and by reference sequence
+#[derive(Debug, Clone)]
+pub struct Coverage {
+    depths: coverage::Depths,
+    files: BTreeSet<usize>,
+    targets: bool,
+}
+
+impl Coverage {
+    /// Create an empty Coverage, depth of files are found in depths
+    pub fn new(depths: coverage::Depths) -> Self {
+        Self {
+            depths,
+            files: BTreeSet::new(),
+            targets: false,
+        }
+    }
+
+    /// Add fields of coverage of targets
+    pub fn with_targets(mut self) -> Self {
+        self.targets = true;
+
+        self
+    }
+
+    /// Create an empty Coverage with same configuration
+    fn same(&self) -> Self {
+        Self {
+            depths: self.depths.clone(),
+            files: BTreeSet::new(),
+            targets: self.targets,
+        }
+    }
+
+    fn merge_state(&mut self, other: &dyn std::any::Any) {
+        let other = other
+            .downcast_ref::<Self>()
+            .expect("Coverage can only be merged with Coverage");
+
+        self.files.extend(other.files.iter());
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let mut depth = coverage::Depth::default();
+        self.files
+            .iter()
+            .filter_map(|i| self.depths.get(*i))
+            .for_each(|d| depth.merge(&d));
+
+        let positions: u64 = depth.references.iter().map(|r| r.positions).sum();
+        let sum: u64 = depth.references.iter().map(|r| r.sum).sum();
+
+        let mut fields = vec![(
+            "coverage_mean".to_string(),
+            Value::Float(sum as f64 / positions as f64),
+        )];
+
+        for (i, threshold) in coverage::THRESHOLDS.iter().enumerate() {
+            let covered: u64 = depth.references.iter().map(|r| r.covered[i]).sum();
+            fields.push((
+                format!("coverage_{}x", threshold),
+                Value::Float(covered as f64 / positions as f64 * 100.0),
+            ));
+        }
+
+        fields.push((
+            "coverage_histogram".to_string(),
+            Value::List(depth.histogram.iter().map(|c| Value::Integer(*c)).collect()),
+        ));
+
+        fields.push((
+            "coverage_by_reference".to_string(),
+            Value::List(depth.references.iter().map(row).collect()),
+        ));
+
+        if self.targets {
+            fields.extend(target_fields(&depth));
+        }
+
+        fields
+    }
+}
+
+/// Fields of coverage of targets, mean depth, breadth of coverage and fold-80 base penalty
+///
+/// Like Picard, fold-80 base penalty is mean depth divide by 20th percentile of depth of
+/// covered positions.
+fn target_fields(depth: &coverage::Depth) -> Vec<Field> {
+    let positions: u64 = depth.target_histogram.iter().sum();
+    let mean = depth.target_sum as f64 / positions as f64;
+
+    let mut fields = vec![("target_coverage_mean".to_string(), Value::Float(mean))];
+
+    for threshold in coverage::THRESHOLDS.iter() {
+        let covered: u64 = depth
+            .target_histogram
+            .iter()
+            .skip(*threshold as usize)
+            .sum();
+        fields.push((
+            format!("target_coverage_{}x", threshold),
+            Value::Float(covered as f64 / positions as f64 * 100.0),
+        ));
+    }
+
+    let covered: u64 = depth.target_histogram.iter().skip(1).sum();
+    let mut cumulative = 0;
+    let percentile = depth
+        .target_histogram
+        .iter()
+        .enumerate()
+        .skip(1)
+        .find(|(_, count)| {
+            cumulative += *count;
+            cumulative as f64 >= covered as f64 * FOLD_80_PERCENTILE
+        })
+        .map(|(depth, _)| depth);
+
+    fields.push((
+        "target_fold_80_penalty".to_string(),
+        Value::Float(match percentile {
+            Some(percentile) if covered != 0 => mean / percentile as f64,
+            _ => f64::NAN,
+        }),
+    ));
+
+    // Targets without position read (outside of regions, ...) aren't report
+    fields.push((
+        "target_coverage_by_target".to_string(),
+        Value::List(
+            depth
+                .targets
+                .iter()
+                .filter(|target| target.positions != 0)
+                .map(row)
+                .collect(),
+        ),
+    ));
+
+    fields
+}
+
+/// Row of a by reference or by target table, name, length, mean depth and breadth of coverage
+fn row(depth: &coverage::ReferenceDepth) -> Value {
+    let mut row = vec![
+        Value::Text(depth.name.clone()),
+        Value::Integer(depth.positions),
+        Value::Float(depth.sum as f64 / depth.positions as f64),
+    ];
+    row.extend(
+        depth
+            .covered
+            .iter()
+            .map(|c| Value::Float(*c as f64 / depth.positions as f64 * 100.0)),
+    );
+
+    Value::List(row)
+}
+
+impl Metric<noodles::bam::Record> for Coverage {
+    fn empty(&self) -> Box<dyn Metric<noodles::bam::Record>> {
+        Box::new(self.same())
+    }
+
+    fn empty_for_file(&self, index: usize) -> Box<dyn Metric<noodles::bam::Record>> {
+        let mut metric = self.same();
+        metric.files.insert(index);
+
+        Box::new(metric)
+    }
+
+    fn observe(&mut self, _record: &noodles::bam::Record) {}
+
+    fn merge(&mut self, other: &dyn Metric<noodles::bam::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    fn depth(name: &str, positions: u64, sum: u64, covered: [u64; 3]) -> coverage::Depth {
+        let mut histogram = vec![0; 3];
+        histogram[0] = positions - covered[0];
+        histogram[2] = covered[0];
+
+        coverage::Depth {
+            references: vec![coverage::ReferenceDepth {
+                name: name.to_string(),
+                positions,
+                sum,
+                covered,
+            }],
+            histogram,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn coverage() {
+        let depths = coverage::Depths::new();
+        depths.insert(0, depth("chr1", 10, 10, [5, 0, 0]));
+        depths.insert(1, depth("chr1", 10, 30, [10, 5, 0]));
+
+        let template = Coverage::new(depths);
+        let first = template.empty_for_file(0);
+        let second = template.empty_for_file(1);
+        let missing = template.empty_for_file(2);
+
+        let fields = first.finalize();
+        assert_eq!(
+            fields[..4],
+            vec![
+                ("coverage_mean".to_string(), Value::Float(1.0)),
+                ("coverage_1x".to_string(), Value::Float(50.0)),
+                ("coverage_10x".to_string(), Value::Float(0.0)),
+                ("coverage_30x".to_string(), Value::Float(0.0)),
+            ][..]
+        );
+        assert_eq!(
+            fields[5],
+            (
+                "coverage_by_reference".to_string(),
+                Value::List(vec![Value::List(vec![
+                    Value::Text("chr1".to_string()),
+                    Value::Integer(10),
+                    Value::Float(1.0),
+                    Value::Float(50.0),
+                    Value::Float(0.0),
+                    Value::Float(0.0),
+                ])])
+            )
+        );
+
+        let mut all = template.empty();
+        all.merge(first.as_ref());
+        all.merge(second.as_ref());
+        all.merge(missing.as_ref());
+
+        let fields = all.finalize();
+        assert_eq!(fields[0], ("coverage_mean".to_string(), Value::Float(2.0)));
+        assert_eq!(fields[2], ("coverage_10x".to_string(), Value::Float(25.0)));
+        assert_eq!(
+            fields[4],
+            (
+                "coverage_histogram".to_string(),
+                Value::List(vec![
+                    Value::Integer(5),
+                    Value::Integer(0),
+                    Value::Integer(15)
+                ])
+            )
+        );
+
+        if let Value::Float(mean) = missing.finalize()[0].1 {
+            assert!(mean.is_nan());
+        } else {
+            panic!("coverage_mean isn't a float");
+        }
+    }
+
+    #[test]
+    fn targets() {
+        let depths = coverage::Depths::new();
+        depths.insert(
+            0,
+            coverage::Depth {
+                targets: vec![
+                    coverage::ReferenceDepth {
+                        name: "a".to_string(),
+                        positions: 10,
+                        sum: 18,
+                        covered: [8, 0, 0],
+                    },
+                    // Target outside of regions read
+                    coverage::ReferenceDepth {
+                        name: "b".to_string(),
+                        ..Default::default()
+                    },
+                ],
+                target_histogram: vec![2, 2, 4, 0, 2],
+                target_sum: 18,
+                ..Default::default()
+            },
+        );
+
+        let fields = Coverage::new(depths.clone()).empty_for_file(0).finalize();
+        assert_eq!(fields.len(), 6);
+
+        let fields = Coverage::new(depths)
+            .with_targets()
+            .empty_for_file(0)
+            .finalize();
+        assert_eq!(
+            fields[6..11],
+            vec![
+                ("target_coverage_mean".to_string(), Value::Float(1.8)),
+                ("target_coverage_1x".to_string(), Value::Float(80.0)),
+                ("target_coverage_10x".to_string(), Value::Float(0.0)),
+                ("target_coverage_30x".to_string(), Value::Float(0.0)),
+                ("target_fold_80_penalty".to_string(), Value::Float(1.8)),
+            ][..]
+        );
+        assert_eq!(
+            fields[11].1,
+            Value::List(vec![Value::List(vec![
+                Value::Text("a".to_string()),
+                Value::Integer(10),
+                Value::Float(1.8),
+                Value::Float(80.0),
+                Value::Float(0.0),
+                Value::Float(0.0),
+            ])])
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Alignment summary from record flags, like samtools flagstat
+//!
+//! Each counter is split between reads that pass quality control and reads flag as QC-fail.
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+use crate::metric::{Field, Metric, Value};
+
+/// Name of counters, in order of Counter
+const NAMES: [&str; NB_COUNTERS] = [
+    "total",
+    "primary",
+    "secondary",
+    "supplementary",
+    "duplicates",
+    "primary_duplicates",
+    "mapped",
+    "primary_mapped",
+    "unmapped",
+    "paired",
+    "read1",
+    "read2",
+    "properly_paired",
+    "with_mate_mapped",
+    "singletons",
+    "mate_diff_chr",
+    "mate_diff_chr_mapq5",
+];
+
+/// Number of counters
+const NB_COUNTERS: usize = 17;
+
+/// Minimal mapping quality of reads count in mate_diff_chr_mapq5
+const MIN_DIFF_CHR_MAPQ: u8 = 5;
+
+/// Index of counters
+#[derive(Debug, Clone, Copy)]
+enum Counter {
+    Total,
+    Primary,
+    Secondary,
+    Supplementary,
+    Duplicates,
+    PrimaryDuplicates,
+    Mapped,
+    PrimaryMapped,
+    Unmapped,
+    Paired,
+    Read1,
+    Read2,
+    ProperlyPaired,
+    WithMateMapped,
+    Singletons,
+    MateDiffChr,
+    MateDiffChrMapq5,
+}
+
+/// Count reads by flags, for QC-pass and QC-fail reads
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flagstat {
+    counts: [[u64; NB_COUNTERS]; 2],
+}
+
+impl Flagstat {
+    /// Create an empty Flagstat
+    pub fn new() -> Self {
+        Self {
+            counts: [[0; NB_COUNTERS]; 2],
+        }
+    }
+
+    fn merge_state(&mut self, other: &dyn std::any::Any) {
+        let other = other
+            .downcast_ref::<Self>()
+            .expect("Flagstat can only be merged with Flagstat");
+
+        for (counts, other_counts) in self.counts.iter_mut().zip(other.counts.iter()) {
+            counts
+                .iter_mut()
+                .zip(other_counts.iter())
+                .for_each(|(c, o)| *c += o);
+        }
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let mut fields = Vec::with_capacity(NB_COUNTERS * 2 + 1);
+
+        for (i, name) in NAMES.iter().enumerate() {
+            fields.push((
+                format!("flagstat_{}_pass", name),
+                Value::Integer(self.counts[0][i]),
+            ));
+            fields.push((
+                format!("flagstat_{}_fail", name),
+                Value::Integer(self.counts[1][i]),
+            ));
+        }
+
+        let pass = &self.counts[0];
+        fields.push((
+            "flagstat_mapped_percent".to_string(),
+            Value::Float(
+                pass[Counter::Mapped as usize] as f64 / pass[Counter::Total as usize] as f64
+                    * 100.0,
+            ),
+        ));
+
+        fields
+    }
+}
+
+impl Default for Flagstat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metric<noodles::bam::Record> for Flagstat {
+    fn empty(&self) -> Box<dyn Metric<noodles::bam::Record>> {
+        Box::new(Self::new())
+    }
+
+    fn observe(&mut self, record: &noodles::bam::Record) {
+        let flags = record.flags();
+        let counts = &mut self.counts[flags.is_qc_fail() as usize];
+        let mut incr = |counter: Counter| counts[counter as usize] += 1;
+
+        incr(Counter::Total);
+
+        if flags.is_secondary() {
+            incr(Counter::Secondary);
+        } else if flags.is_supplementary() {
+            incr(Counter::Supplementary);
+        } else {
+            incr(Counter::Primary);
+
+            if flags.is_segmented() {
+                incr(Counter::Paired);
+
+                if flags.is_properly_aligned() && !flags.is_unmapped() {
+                    incr(Counter::ProperlyPaired);
+                }
+                if flags.is_first_segment() {
+                    incr(Counter::Read1);
+                }
+                if flags.is_last_segment() {
+                    incr(Counter::Read2);
+                }
+
+                if !flags.is_unmapped() {
+                    if flags.is_mate_unmapped() {
+                        incr(Counter::Singletons);
+                    } else {
+                        incr(Counter::WithMateMapped);
+
+                        if record.reference_sequence_id() != record.mate_reference_sequence_id() {
+                            incr(Counter::MateDiffChr);
+
+                            if record
+                                .mapping_quality()
+                                .map(u8::from)
+                                .map_or(false, |mapq| mapq >= MIN_DIFF_CHR_MAPQ)
+                            {
+                                incr(Counter::MateDiffChrMapq5);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if !flags.is_unmapped() {
+                incr(Counter::PrimaryMapped);
+            }
+            if flags.is_duplicate() {
+                incr(Counter::PrimaryDuplicates);
+            }
+        }
+
+        if flags.is_unmapped() {
+            incr(Counter::Unmapped);
+        } else {
+            incr(Counter::Mapped);
+        }
+        if flags.is_duplicate() {
+            incr(Counter::Duplicates);
+        }
+    }
+
+    fn merge(&mut self, other: &dyn Metric<noodles::bam::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use noodles::sam::record::Flags;
+
+    fn record(
+        flags: Flags,
+        reference_id: i32,
+        mate_reference_id: i32,
+        mapq: u8,
+    ) -> noodles::bam::Record {
+        let mut record = noodles::bam::Record::default();
+
+        *record.flags_mut() = flags;
+        *record.reference_sequence_id_mut() = reference_id.try_into().ok();
+        *record.mate_reference_sequence_id_mut() = mate_reference_id.try_into().ok();
+        *record.mapping_quality_mut() = mapq.try_into().ok();
+
+        record
+    }
+
+    fn get(fields: &[Field], name: &str) -> Value {
+        fields
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn flagstat() {
+        let paired = Flags::SEGMENTED | Flags::PROPERLY_ALIGNED;
+
+        let mut metric = Flagstat::new();
+        for record in [
+            record(paired | Flags::FIRST_SEGMENT, 0, 0, 60),
+            record(paired | Flags::LAST_SEGMENT | Flags::DUPLICATE, 0, 0, 60),
+            record(
+                Flags::SEGMENTED | Flags::FIRST_SEGMENT | Flags::MATE_UNMAPPED,
+                0,
+                -1,
+                60,
+            ),
+            record(
+                Flags::SEGMENTED | Flags::LAST_SEGMENT | Flags::UNMAPPED,
+                -1,
+                0,
+                255,
+            ),
+            record(Flags::SEGMENTED | Flags::FIRST_SEGMENT, 0, 1, 10),
+            record(Flags::SEGMENTED | Flags::LAST_SEGMENT, 1, 0, 2),
+            record(Flags::SECONDARY, 0, -1, 0),
+            record(Flags::SUPPLEMENTARY, 0, -1, 60),
+            record(Flags::UNMAPPED | Flags::QC_FAIL, -1, -1, 255),
+        ] {
+            metric.observe(&record);
+        }
+
+        let mut other = Flagstat::new();
+        other.observe(&record(Flags::QC_FAIL | Flags::DUPLICATE, 0, -1, 60));
+        metric.merge(&other);
+
+        let fields = metric.finalize();
+
+        assert_eq!(fields.len(), NB_COUNTERS * 2 + 1);
+        assert_eq!(get(&fields, "flagstat_total_pass"), Value::Integer(8));
+        assert_eq!(get(&fields, "flagstat_total_fail"), Value::Integer(2));
+        assert_eq!(get(&fields, "flagstat_primary_pass"), Value::Integer(6));
+        assert_eq!(get(&fields, "flagstat_secondary_pass"), Value::Integer(1));
+        assert_eq!(
+            get(&fields, "flagstat_supplementary_pass"),
+            Value::Integer(1)
+        );
+        assert_eq!(get(&fields, "flagstat_duplicates_pass"), Value::Integer(1));
+        assert_eq!(get(&fields, "flagstat_duplicates_fail"), Value::Integer(1));
+        assert_eq!(
+            get(&fields, "flagstat_primary_duplicates_pass"),
+            Value::Integer(1)
+        );
+        assert_eq!(get(&fields, "flagstat_mapped_pass"), Value::Integer(7));
+        assert_eq!(get(&fields, "flagstat_mapped_fail"), Value::Integer(1));
+        assert_eq!(
+            get(&fields, "flagstat_primary_mapped_pass"),
+            Value::Integer(5)
+        );
+        assert_eq!(get(&fields, "flagstat_unmapped_pass"), Value::Integer(1));
+        assert_eq!(get(&fields, "flagstat_unmapped_fail"), Value::Integer(1));
+        assert_eq!(get(&fields, "flagstat_paired_pass"), Value::Integer(6));
+        assert_eq!(get(&fields, "flagstat_read1_pass"), Value::Integer(3));
+        assert_eq!(get(&fields, "flagstat_read2_pass"), Value::Integer(3));
+        assert_eq!(
+            get(&fields, "flagstat_properly_paired_pass"),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            get(&fields, "flagstat_with_mate_mapped_pass"),
+            Value::Integer(4)
+        );
+        assert_eq!(get(&fields, "flagstat_singletons_pass"), Value::Integer(1));
+        assert_eq!(
+            get(&fields, "flagstat_mate_diff_chr_pass"),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            get(&fields, "flagstat_mate_diff_chr_mapq5_pass"),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            get(&fields, "flagstat_mapped_percent"),
+            Value::Float(7.0 / 8.0 * 100.0)
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Per read GC content distribution
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+use crate::count;
+use crate::input::fastq;
+use crate::metric::{Field, Metric, Value};
+
+/// Number of bin in GC histogram, one by percent
+pub const NB_BINS: usize = 101;
+
+/// Histogram of GC percent of each read
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gc {
+    histogram: [u64; NB_BINS],
+}
+
+impl Gc {
+    /// Create an empty Gc
+    pub fn new() -> Self {
+        Self {
+            histogram: [0; NB_BINS],
+        }
+    }
+
+    /// Add a read with gc G or C base on acgt A, C, G or T base, read without ACGT is ignored
+    fn add(&mut self, gc: u64, acgt: u64) {
+        if acgt != 0 {
+            let percent = (gc as f64 / acgt as f64 * 100.0).round() as usize;
+
+            self.histogram[percent] += 1;
+        }
+    }
+
+    fn merge_state(&mut self, other: &dyn std::any::Any) {
+        let other = other
+            .downcast_ref::<Self>()
+            .expect("Gc can only be merged with Gc");
+
+        self.histogram
+            .iter_mut()
+            .zip(other.histogram.iter())
+            .for_each(|(a, b)| *a += b);
+    }
+
+    /// Most frequent GC percent
+    fn mode(&self) -> u64 {
+        self.histogram
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .map(|(percent, _)| percent as u64)
+            .unwrap_or(0)
+    }
+
+    /// Percentage of reads out of a normal distribution with same mean and standard deviation
+    fn deviation(&self) -> f64 {
+        let total: u64 = self.histogram.iter().sum();
+        if total == 0 {
+            return f64::NAN;
+        }
+
+        let mean = self
+            .histogram
+            .iter()
+            .enumerate()
+            .map(|(percent, count)| percent as f64 * *count as f64)
+            .sum::<f64>()
+            / total as f64;
+        let variance = self
+            .histogram
+            .iter()
+            .enumerate()
+            .map(|(percent, count)| (percent as f64 - mean).powi(2) * *count as f64)
+            .sum::<f64>()
+            / total as f64;
+
+        if variance == 0.0 {
+            return 0.0;
+        }
+
+        let theoretical = |percent: f64| {
+            total as f64 * (-(percent - mean).powi(2) / (2.0 * variance)).exp()
+                / (2.0 * std::f64::consts::PI * variance).sqrt()
+        };
+
+        self.histogram
+            .iter()
+            .enumerate()
+            .map(|(percent, count)| (*count as f64 - theoretical(percent as f64)).abs())
+            .sum::<f64>()
+            / total as f64
+            * 100.0
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        vec![
+            ("gc_mode".to_string(), Value::Integer(self.mode())),
+            ("gc_deviation".to_string(), Value::Float(self.deviation())),
+            (
+                "gc_histogram".to_string(),
+                Value::List(self.histogram.iter().map(|c| Value::Integer(*c)).collect()),
+            ),
+        ]
+    }
+}
+
+impl Default for Gc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metric<fastq::Record> for Gc {
+    fn empty(&self) -> Box<dyn Metric<fastq::Record>> {
+        Box::new(Self::new())
+    }
+
+    fn observe(&mut self, record: &fastq::Record) {
+        let counts = count::count(record.sequence());
+
+        self.add(counts.gc(), counts.acgt())
+    }
+
+    fn merge(&mut self, other: &dyn Metric<fastq::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl Metric<noodles::bam::Record> for Gc {
+    fn empty(&self) -> Box<dyn Metric<noodles::bam::Record>> {
+        Box::new(Self::new())
+    }
+
+    fn observe(&mut self, record: &noodles::bam::Record) {
+        let mut gc = 0;
+        let mut acgt = 0;
+
+        for n in record.sequence().bases() {
+            match n {
+                noodles::bam::record::sequence::Base::A
+                | noodles::bam::record::sequence::Base::T => acgt += 1,
+                noodles::bam::record::sequence::Base::C
+                | noodles::bam::record::sequence::Base::G => {
+                    gc += 1;
+                    acgt += 1
+                }
+                _ => (),
+            }
+        }
+
+        self.add(gc, acgt)
+    }
+
+    fn merge(&mut self, other: &dyn Metric<noodles::bam::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn fastq() {
+        let mut metric = Gc::new();
+        Metric::<fastq::Record>::observe(
+            &mut metric,
+            &fastq::Record::new(b"ACTGN".to_vec(), b"!!!!!".to_vec()),
+        );
+        Metric::<fastq::Record>::observe(
+            &mut metric,
+            &fastq::Record::new(b"GGGC".to_vec(), b"!!!!".to_vec()),
+        );
+        Metric::<fastq::Record>::observe(
+            &mut metric,
+            &fastq::Record::new(b"NNN".to_vec(), b"!!!".to_vec()),
+        );
+
+        let mut other = Gc::new();
+        Metric::<fastq::Record>::observe(
+            &mut other,
+            &fastq::Record::new(b"AAAG".to_vec(), b"!!!!".to_vec()),
+        );
+
+        metric.merge_state(&other);
+
+        assert_eq!(metric.histogram[50], 1);
+        assert_eq!(metric.histogram[100], 1);
+        assert_eq!(metric.histogram[25], 1);
+        assert_eq!(metric.histogram.iter().sum::<u64>(), 3);
+        assert_eq!(metric.mode(), 25);
+    }
+
+    #[test]
+    fn bam() {
+        let record = noodles::bam::Record::try_from_sam_record(
+            noodles::sam::Header::builder()
+                .build()
+                .reference_sequences(),
+            &noodles::sam::Record::builder()
+                .set_read_name("1".parse().unwrap())
+                .set_flags(noodles::sam::record::Flags::UNMAPPED)
+                .set_sequence("ACCGN".parse().unwrap())
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let mut metric = Gc::new();
+        Metric::<noodles::bam::Record>::observe(&mut metric, &record);
+
+        assert_eq!(metric.histogram[75], 1);
+        assert_eq!(metric.mode(), 75);
+        assert_eq!(metric.deviation(), 0.0);
+    }
+
+    #[test]
+    fn deviation() {
+        assert!(Gc::new().deviation().is_nan());
+
+        // A normal distribution of mean 50 and standard deviation 5
+        let mut normal = Gc::new();
+        for (percent, count) in normal.histogram.iter_mut().enumerate() {
+            *count = (100_000.0 * (-((percent as f64 - 50.0).powi(2)) / 50.0).exp()
+                / (50.0 * std::f64::consts::PI).sqrt())
+            .round() as u64;
+        }
+        assert_eq!(normal.mode(), 50);
+        assert!(normal.deviation() < 1.0);
+
+        // A bimodal distribution
+        let mut bimodal = Gc::new();
+        bimodal.histogram[30] = 1000;
+        bimodal.histogram[70] = 1000;
+        assert_eq!(bimodal.mode(), 30);
+        assert!(bimodal.deviation() > 50.0);
+    }
+}
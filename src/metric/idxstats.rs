@@ -0,0 +1,342 @@
+//! Number of reads and aligned bases by reference sequence, like samtools idxstats
+//!
+//! Reference sequences are identify by name, so files with different dictionary can be
+//! aggregate.
+
+/* std use */
+
+/* crates use */
+use noodles::sam::record::cigar::op::Kind;
+
+/* project use */
+use crate::input::dictionary;
+use crate::metric::{Field, Metric, Value};
+
+/// Name of reference sequence of unmapped reads without coordinate
+const UNPLACED: &str = "*";
+
+/// Counts of one reference sequence
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Reference {
+    name: String,
+    length: u64,
+    mapped: u64,
+    unmapped: u64,
+    bases: u64,
+}
+
+impl Reference {
+    fn new(name: String, length: u64) -> Self {
+        Self {
+            name,
+            length,
+            ..Default::default()
+        }
+    }
+}
+
+/// Count mapped reads, unmapped reads and aligned bases of each reference sequence
+///
+/// Like samtools idxstats, all records are count, secondary and supplementary too. Unmapped
+/// reads placed on a reference sequence (with a mapped mate) are count on this reference.
+#[derive(Debug, Clone)]
+pub struct Idxstats {
+    dictionaries: dictionary::Dictionaries,
+    file: Option<usize>,
+    references: Vec<Reference>,
+    unplaced: u64,
+}
+
+impl Idxstats {
+    /// Create an empty Idxstats, names of reference sequences are found in dictionaries
+    pub fn new(dictionaries: dictionary::Dictionaries) -> Self {
+        Self {
+            dictionaries,
+            file: None,
+            references: Vec::new(),
+            unplaced: 0,
+        }
+    }
+
+    /// Get reference sequence of id, dictionary of file is load at first call
+    fn reference_mut(&mut self, id: usize) -> &mut Reference {
+        if id >= self.references.len() {
+            if let Some(dictionary) = self.file.and_then(|i| self.dictionaries.get(i)) {
+                self.references.extend(
+                    dictionary[self.references.len().min(dictionary.len())..]
+                        .iter()
+                        .map(|(name, length)| Reference::new(name.clone(), *length)),
+                );
+            }
+
+            // Reference isn't in dictionary, use id as name
+            while id >= self.references.len() {
+                let name = self.references.len().to_string();
+                self.references.push(Reference::new(name, 0));
+            }
+        }
+
+        &mut self.references[id]
+    }
+
+    /// Counts of all reference sequences, reference sequences of file dictionary without reads
+    /// are add with zero counts
+    fn complete(&self) -> Vec<Reference> {
+        let mut references = self.references.clone();
+
+        if let Some(dictionary) = self.file.and_then(|i| self.dictionaries.get(i)) {
+            references.extend(
+                dictionary[references.len().min(dictionary.len())..]
+                    .iter()
+                    .map(|(name, length)| Reference::new(name.clone(), *length)),
+            );
+        }
+
+        references
+    }
+
+    fn merge_state(&mut self, other: &dyn std::any::Any) {
+        let other = other
+            .downcast_ref::<Self>()
+            .expect("Idxstats can only be merged with Idxstats");
+
+        for (i, reference) in other.complete().iter().enumerate() {
+            let position = if self
+                .references
+                .get(i)
+                .map_or(false, |r| r.name == reference.name)
+            {
+                Some(i)
+            } else {
+                self.references
+                    .iter()
+                    .position(|r| r.name == reference.name)
+            };
+
+            match position {
+                Some(position) => {
+                    let target = &mut self.references[position];
+                    target.length = target.length.max(reference.length);
+                    target.mapped += reference.mapped;
+                    target.unmapped += reference.unmapped;
+                    target.bases += reference.bases;
+                }
+                None => self.references.push(reference.clone()),
+            }
+        }
+
+        self.unplaced += other.unplaced;
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let mut table: Vec<Value> = self
+            .complete()
+            .iter()
+            .map(|r| {
+                Value::List(vec![
+                    Value::Text(r.name.clone()),
+                    Value::Integer(r.length),
+                    Value::Integer(r.mapped),
+                    Value::Integer(r.unmapped),
+                    Value::Integer(r.bases),
+                ])
+            })
+            .collect();
+
+        table.push(Value::List(vec![
+            Value::Text(UNPLACED.to_string()),
+            Value::Integer(0),
+            Value::Integer(0),
+            Value::Integer(self.unplaced),
+            Value::Integer(0),
+        ]));
+
+        vec![("idxstats".to_string(), Value::List(table))]
+    }
+}
+
+impl Metric<noodles::bam::Record> for Idxstats {
+    fn empty(&self) -> Box<dyn Metric<noodles::bam::Record>> {
+        Box::new(Self::new(self.dictionaries.clone()))
+    }
+
+    fn empty_for_file(&self, index: usize) -> Box<dyn Metric<noodles::bam::Record>> {
+        let mut metric = Self::new(self.dictionaries.clone());
+        metric.file = Some(index);
+
+        Box::new(metric)
+    }
+
+    fn observe(&mut self, record: &noodles::bam::Record) {
+        let id = match record.reference_sequence_id() {
+            Some(id) => i32::from(id) as usize,
+            None => {
+                self.unplaced += 1;
+                return;
+            }
+        };
+
+        if record.flags().is_unmapped() {
+            self.reference_mut(id).unmapped += 1;
+        } else {
+            let bases: u64 = record
+                .cigar()
+                .ops()
+                .filter_map(|op| op.ok())
+                .filter(|op| matches!(op.kind(), Kind::Match | Kind::SeqMatch | Kind::SeqMismatch))
+                .map(|op| op.len() as u64)
+                .sum();
+
+            let reference = self.reference_mut(id);
+            reference.mapped += 1;
+            reference.bases += bases;
+        }
+    }
+
+    fn merge(&mut self, other: &dyn Metric<noodles::bam::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use noodles::bam::record::cigar::Op;
+    use noodles::sam::record::Flags;
+
+    fn record(flags: Flags, reference_id: i32, cigar: &[Op]) -> noodles::bam::Record {
+        let mut record = noodles::bam::Record::default();
+
+        *record.flags_mut() = flags;
+        *record.reference_sequence_id_mut() = reference_id.try_into().ok();
+        for op in cigar {
+            record.cigar_mut().push(*op);
+        }
+
+        record
+    }
+
+    fn row(name: &str, length: u64, mapped: u64, unmapped: u64, bases: u64) -> Value {
+        Value::List(vec![
+            Value::Text(name.to_string()),
+            Value::Integer(length),
+            Value::Integer(mapped),
+            Value::Integer(unmapped),
+            Value::Integer(bases),
+        ])
+    }
+
+    #[test]
+    fn idxstats() {
+        let dictionaries = dictionary::Dictionaries::new();
+        dictionaries.insert(
+            0,
+            vec![
+                ("chr1".to_string(), 100),
+                ("chrM".to_string(), 16),
+                ("chrX".to_string(), 80),
+            ],
+        );
+        dictionaries.insert(1, vec![("chrM".to_string(), 16), ("chrY".to_string(), 50)]);
+
+        let template = Idxstats::new(dictionaries);
+        let cigar = [
+            Op::new(Kind::SoftClip, 2).unwrap(),
+            Op::new(Kind::Match, 10).unwrap(),
+            Op::new(Kind::Deletion, 3).unwrap(),
+            Op::new(Kind::SeqMatch, 5).unwrap(),
+        ];
+
+        let mut first = template.empty_for_file(0);
+        first.observe(&record(Flags::empty(), 0, &cigar));
+        first.observe(&record(Flags::SECONDARY, 0, &cigar));
+        first.observe(&record(Flags::UNMAPPED, 1, &[]));
+        first.observe(&record(Flags::UNMAPPED, -1, &[]));
+
+        let mut partial = template.empty_for_file(0);
+        partial.observe(&record(Flags::empty(), 1, &cigar[1..2]));
+        first.merge(partial.as_ref());
+
+        assert_eq!(
+            first.finalize(),
+            vec![(
+                "idxstats".to_string(),
+                Value::List(vec![
+                    row("chr1", 100, 2, 0, 30),
+                    row("chrM", 16, 1, 1, 10),
+                    row("chrX", 80, 0, 0, 0),
+                    row("*", 0, 0, 1, 0),
+                ])
+            )]
+        );
+
+        let mut second = template.empty_for_file(1);
+        second.observe(&record(Flags::empty(), 1, &cigar));
+        second.observe(&record(Flags::empty(), 0, &cigar));
+
+        let mut all = template.empty();
+        all.merge(first.as_ref());
+        all.merge(second.as_ref());
+
+        assert_eq!(
+            all.finalize(),
+            vec![(
+                "idxstats".to_string(),
+                Value::List(vec![
+                    row("chr1", 100, 2, 0, 30),
+                    row("chrM", 16, 2, 1, 25),
+                    row("chrX", 80, 0, 0, 0),
+                    row("chrY", 50, 1, 0, 15),
+                    row("*", 0, 0, 1, 0),
+                ])
+            )]
+        );
+    }
+
+    #[test]
+    fn without_reads() {
+        let dictionaries = dictionary::Dictionaries::new();
+        dictionaries.insert(0, vec![("chr1".to_string(), 100), ("chr2".to_string(), 50)]);
+
+        let metric = Idxstats::new(dictionaries).empty_for_file(0);
+
+        assert_eq!(
+            metric.finalize(),
+            vec![(
+                "idxstats".to_string(),
+                Value::List(vec![
+                    row("chr1", 100, 0, 0, 0),
+                    row("chr2", 50, 0, 0, 0),
+                    row("*", 0, 0, 0, 0),
+                ])
+            )]
+        );
+    }
+
+    #[test]
+    fn without_dictionary() {
+        let mut metric = Idxstats::new(dictionary::Dictionaries::new());
+        metric.observe(&record(Flags::UNMAPPED, 1, &[]));
+
+        assert_eq!(
+            metric.finalize(),
+            vec![(
+                "idxstats".to_string(),
+                Value::List(vec![
+                    row("0", 0, 0, 0, 0),
+                    row("1", 0, 0, 1, 0),
+                    row("*", 0, 0, 0, 0),
+                ])
+            )]
+        );
+    }
+}
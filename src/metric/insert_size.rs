@@ -0,0 +1,439 @@
+//! Insert size distribution of paired-end alignments, by read group and pair orientation
+
+/* std use */
+use std::collections::BTreeMap;
+
+/* crates use */
+
+/* project use */
+use crate::metric::{Field, Metric, Value};
+
+/// Read group of records without RG tag
+const NO_READ_GROUP: &str = "*";
+
+/// Histogram is write up to median + DEVIATIONS * MAD, same value as Picard
+const DEVIATIONS: f64 = 10.0;
+
+/// Orientation of a pair, like Picard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Orientation {
+    /// Forward read is before reverse read
+    Fr,
+    /// Reverse read is before forward read
+    Rf,
+    /// Both reads are on same strand
+    Tandem,
+}
+
+impl std::fmt::Display for Orientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Orientation::Fr => write!(f, "FR"),
+            Orientation::Rf => write!(f, "RF"),
+            Orientation::Tandem => write!(f, "TANDEM"),
+        }
+    }
+}
+
+/// Get orientation of pair of a record, 5' end of reverse read is compute with template length
+fn orientation(record: &noodles::bam::Record) -> Option<Orientation> {
+    let flags = record.flags();
+    if flags.is_reverse_complemented() == flags.is_mate_reverse_complemented() {
+        return Some(Orientation::Tandem);
+    }
+
+    let start = i32::from(record.position()?);
+    let (forward, reverse) = if flags.is_reverse_complemented() {
+        let end = start + record.cigar().reference_len().unwrap_or(0).max(1) as i32 - 1;
+        (i32::from(record.mate_position()?), end)
+    } else {
+        (start, start + record.template_length())
+    };
+
+    if reverse > forward {
+        Some(Orientation::Fr)
+    } else {
+        Some(Orientation::Rf)
+    }
+}
+
+/// Get read group of a record, [NO_READ_GROUP] if record haven't RG tag
+fn read_group(record: &noodles::bam::Record) -> String {
+    record
+        .data()
+        .get(noodles::sam::record::data::field::Tag::ReadGroup)
+        .and_then(|field| field.ok())
+        .and_then(|field| field.value().as_str().map(|s| s.to_string()))
+        .unwrap_or_else(|| NO_READ_GROUP.to_string())
+}
+
+/// Median of weighted values sorted by value, NaN if there are no value
+fn median(values: &[(f64, u64)]) -> f64 {
+    let total: u64 = values.iter().map(|(_, c)| c).sum();
+    if total == 0 {
+        return f64::NAN;
+    }
+
+    let nth = |n: u64| {
+        let mut cumulative = 0;
+        values
+            .iter()
+            .find(|(_, c)| {
+                cumulative += c;
+                cumulative > n
+            })
+            .map(|(v, _)| *v)
+            .unwrap_or(f64::NAN)
+    };
+
+    if total % 2 == 1 {
+        nth(total / 2)
+    } else {
+        (nth(total / 2 - 1) + nth(total / 2)) / 2.0
+    }
+}
+
+/// Median, median absolute deviation and mode of an insert size histogram
+fn summary(histogram: &BTreeMap<u64, u64>) -> (f64, f64, f64) {
+    let values: Vec<(f64, u64)> = histogram.iter().map(|(s, c)| (*s as f64, *c)).collect();
+    let center = median(&values);
+
+    let mut deviations: Vec<(f64, u64)> = values
+        .iter()
+        .map(|(s, c)| ((s - center).abs(), *c))
+        .collect();
+    deviations.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    let mad = median(&deviations);
+
+    let mode = histogram
+        .iter()
+        .fold(None, |best: Option<(u64, u64)>, (s, c)| match best {
+            Some((_, best_c)) if best_c >= *c => best,
+            _ => Some((*s, *c)),
+        })
+        .map_or(f64::NAN, |(s, _)| s as f64);
+
+    (center, mad, mode)
+}
+
+/// Histogram of absolute template length of properly paired first segment, by read group and
+/// orientation, secondary, supplementary, QC-fail and duplicate records are ignored
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InsertSize {
+    histograms: BTreeMap<(String, Orientation), BTreeMap<u64, u64>>,
+}
+
+impl InsertSize {
+    /// Create an empty InsertSize
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn merge_state(&mut self, other: &dyn std::any::Any) {
+        let other = other
+            .downcast_ref::<Self>()
+            .expect("InsertSize can only be merged with InsertSize");
+
+        for (key, histogram) in other.histograms.iter() {
+            let local = self.histograms.entry(key.clone()).or_default();
+            for (size, count) in histogram.iter() {
+                *local.entry(*size).or_insert(0) += count;
+            }
+        }
+    }
+
+    /// Histogram of all read groups by orientation
+    fn by_orientation(&self) -> BTreeMap<Orientation, BTreeMap<u64, u64>> {
+        let mut orientations: BTreeMap<Orientation, BTreeMap<u64, u64>> = BTreeMap::new();
+        for ((_, orientation), histogram) in self.histograms.iter() {
+            let local = orientations.entry(*orientation).or_default();
+            for (size, count) in histogram.iter() {
+                *local.entry(*size).or_insert(0) += count;
+            }
+        }
+
+        orientations
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let orientations = self.by_orientation();
+        let pairs: u64 = orientations.values().flat_map(|h| h.values()).sum();
+
+        // summary is compute on orientation with most pairs
+        let main = orientations.iter().max_by_key(|(orientation, h)| {
+            (h.values().sum::<u64>(), std::cmp::Reverse(**orientation))
+        });
+        let empty = BTreeMap::new();
+        let histogram = main.map_or(&empty, |(_, h)| h);
+        let (median, mad, mode) = summary(histogram);
+
+        let width = if median.is_nan() {
+            0
+        } else {
+            let max = histogram.keys().next_back().copied().unwrap_or(0);
+            ((median + DEVIATIONS * mad) as u64).min(max) + 1
+        };
+        let mut dense = vec![0; width as usize];
+        for (size, count) in histogram.range(..width) {
+            dense[*size as usize] = *count;
+        }
+
+        vec![
+            ("insert_size_pairs".to_string(), Value::Integer(pairs)),
+            (
+                "insert_size_orientation".to_string(),
+                Value::Text(main.map_or("NA".to_string(), |(o, _)| o.to_string())),
+            ),
+            ("insert_size_median".to_string(), Value::Float(median)),
+            ("insert_size_mad".to_string(), Value::Float(mad)),
+            ("insert_size_mode".to_string(), Value::Float(mode)),
+            (
+                "insert_size_histogram".to_string(),
+                Value::List(dense.into_iter().map(Value::Integer).collect()),
+            ),
+            (
+                "insert_size_by_read_group".to_string(),
+                Value::List(
+                    self.histograms
+                        .iter()
+                        .map(|((group, orientation), histogram)| {
+                            let (median, mad, mode) = summary(histogram);
+                            Value::List(vec![
+                                Value::Text(group.clone()),
+                                Value::Text(orientation.to_string()),
+                                Value::Integer(histogram.values().sum()),
+                                Value::Float(median),
+                                Value::Float(mad),
+                                Value::Float(mode),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ]
+    }
+}
+
+impl Metric<noodles::bam::Record> for InsertSize {
+    fn empty(&self) -> Box<dyn Metric<noodles::bam::Record>> {
+        Box::new(Self::new())
+    }
+
+    fn observe(&mut self, record: &noodles::bam::Record) {
+        let flags = record.flags();
+        if !flags.is_segmented()
+            || !flags.is_properly_aligned()
+            || !flags.is_first_segment()
+            || flags.is_unmapped()
+            || flags.is_mate_unmapped()
+            || flags.is_secondary()
+            || flags.is_supplementary()
+            || flags.is_qc_fail()
+            || flags.is_duplicate()
+            || record.template_length() == 0
+        {
+            return;
+        }
+
+        if let Some(orientation) = orientation(record) {
+            *self
+                .histograms
+                .entry((read_group(record), orientation))
+                .or_default()
+                .entry(record.template_length().unsigned_abs() as u64)
+                .or_insert(0) += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &dyn Metric<noodles::bam::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use noodles::sam::record::Flags;
+
+    const PAIR: Flags = Flags::SEGMENTED
+        .union(Flags::PROPERLY_ALIGNED)
+        .union(Flags::FIRST_SEGMENT);
+
+    fn record(flags: Flags, start: i32, mate: i32, tlen: i32, group: &str) -> noodles::bam::Record {
+        let header = noodles::sam::Header::builder()
+            .add_reference_sequence(
+                noodles::sam::header::ReferenceSequence::new("chr1".parse().unwrap(), 2000)
+                    .unwrap(),
+            )
+            .build();
+
+        let mut builder = noodles::sam::Record::builder()
+            .set_flags(flags)
+            .set_reference_sequence_name("chr1".parse().unwrap())
+            .set_position(start.try_into().unwrap())
+            .set_cigar("10M".parse().unwrap())
+            .set_mate_reference_sequence_name("chr1".parse().unwrap())
+            .set_mate_position(mate.try_into().unwrap())
+            .set_template_length(tlen);
+        if !group.is_empty() {
+            builder = builder.set_data(format!("RG:Z:{}", group).parse().unwrap());
+        }
+
+        noodles::bam::Record::try_from_sam_record(
+            header.reference_sequences(),
+            &builder.build().unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn orientation_() {
+        assert_eq!(
+            orientation(&record(
+                PAIR | Flags::MATE_REVERSE_COMPLEMENTED,
+                100,
+                200,
+                110,
+                ""
+            )),
+            Some(Orientation::Fr)
+        );
+        assert_eq!(
+            orientation(&record(
+                PAIR | Flags::REVERSE_COMPLEMENTED,
+                200,
+                100,
+                -110,
+                ""
+            )),
+            Some(Orientation::Fr)
+        );
+        assert_eq!(
+            orientation(&record(
+                PAIR | Flags::REVERSE_COMPLEMENTED,
+                100,
+                200,
+                110,
+                ""
+            )),
+            Some(Orientation::Rf)
+        );
+        assert_eq!(
+            orientation(&record(PAIR, 100, 200, 110, "")),
+            Some(Orientation::Tandem)
+        );
+    }
+
+    #[test]
+    fn summary_() {
+        let histogram: BTreeMap<u64, u64> = [(100, 2), (110, 1), (150, 1)].into_iter().collect();
+        assert_eq!(summary(&histogram), (105.0, 5.0, 100.0));
+
+        let (median, mad, mode) = summary(&BTreeMap::new());
+        assert!(median.is_nan() && mad.is_nan() && mode.is_nan());
+    }
+
+    #[test]
+    fn insert_size() {
+        let fr = PAIR | Flags::MATE_REVERSE_COMPLEMENTED;
+
+        let mut metric = InsertSize::new();
+        for record in [
+            record(fr, 100, 190, 100, "a"),
+            record(fr, 100, 190, 100, "a"),
+            record(fr, 100, 190, 102, "b"),
+            record(PAIR | Flags::REVERSE_COMPLEMENTED, 100, 190, 100, "a"),
+            record(fr - Flags::PROPERLY_ALIGNED, 100, 190, 100, "a"),
+            record(
+                (fr - Flags::FIRST_SEGMENT) | Flags::LAST_SEGMENT,
+                100,
+                190,
+                100,
+                "a",
+            ),
+            record(fr | Flags::DUPLICATE, 100, 190, 100, "a"),
+            record(fr, 100, 190, 0, "a"),
+        ] {
+            metric.observe(&record);
+        }
+
+        let mut other = InsertSize::new();
+        other.observe(&record(fr, 100, 190, 104, ""));
+        metric.merge(&other);
+
+        let fields = metric.finalize();
+        assert_eq!(
+            fields[..5],
+            vec![
+                ("insert_size_pairs".to_string(), Value::Integer(5)),
+                (
+                    "insert_size_orientation".to_string(),
+                    Value::Text("FR".to_string())
+                ),
+                ("insert_size_median".to_string(), Value::Float(101.0)),
+                ("insert_size_mad".to_string(), Value::Float(1.0)),
+                ("insert_size_mode".to_string(), Value::Float(100.0)),
+            ][..]
+        );
+
+        if let Value::List(histogram) = &fields[5].1 {
+            assert_eq!(histogram.len(), 105);
+            assert_eq!(histogram[100], Value::Integer(2));
+        } else {
+            panic!("insert_size_histogram isn't a list");
+        }
+
+        assert_eq!(
+            fields[6].1,
+            Value::List(vec![
+                Value::List(vec![
+                    Value::Text("*".to_string()),
+                    Value::Text("FR".to_string()),
+                    Value::Integer(1),
+                    Value::Float(104.0),
+                    Value::Float(0.0),
+                    Value::Float(104.0),
+                ]),
+                Value::List(vec![
+                    Value::Text("a".to_string()),
+                    Value::Text("FR".to_string()),
+                    Value::Integer(2),
+                    Value::Float(100.0),
+                    Value::Float(0.0),
+                    Value::Float(100.0),
+                ]),
+                Value::List(vec![
+                    Value::Text("a".to_string()),
+                    Value::Text("RF".to_string()),
+                    Value::Integer(1),
+                    Value::Float(100.0),
+                    Value::Float(0.0),
+                    Value::Float(100.0),
+                ]),
+                Value::List(vec![
+                    Value::Text("b".to_string()),
+                    Value::Text("FR".to_string()),
+                    Value::Integer(1),
+                    Value::Float(102.0),
+                    Value::Float(0.0),
+                    Value::Float(102.0),
+                ]),
+            ])
+        );
+
+        if let Value::Float(median) = InsertSize::new().finalize()[2].1 {
+            assert!(median.is_nan());
+        } else {
+            panic!("insert_size_median isn't a float");
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Length of records
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+use crate::input::fastq;
+use crate::metric::{Field, Metric, Value};
+
+/// Length under this limit are store exactly in histogram
+pub const EXACT_LIMIT: u64 = 1 << 16;
+
+/// Number of most significant bits keep for length greater or equal to [EXACT_LIMIT]
+pub const PRECISION_BITS: u32 = 12;
+
+/// Get histogram bin of a length, length over [EXACT_LIMIT] are log binned with a relative
+/// error lower than 2^-[PRECISION_BITS]
+pub fn bin(length: u64) -> u64 {
+    if length < EXACT_LIMIT {
+        length
+    } else {
+        let shift = 64 - length.leading_zeros() - PRECISION_BITS;
+
+        (length >> shift) << shift
+    }
+}
+
+/// Count number of records, their total length and build length histogram
+#[derive(Debug, Clone, PartialEq)]
+pub struct Length {
+    nb_records: u64,
+    total: u64,
+    min: u64,
+    max: u64,
+    histogram: std::collections::BTreeMap<u64, u64>,
+}
+
+impl Length {
+    /// Create an empty Length
+    pub fn new() -> Self {
+        Self {
+            nb_records: 0,
+            total: 0,
+            min: u64::MAX,
+            max: 0,
+            histogram: std::collections::BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, length: usize) {
+        let length = length as u64;
+
+        self.nb_records += 1;
+        self.total += length;
+        self.min = self.min.min(length);
+        self.max = self.max.max(length);
+        *self.histogram.entry(bin(length)).or_insert(0) += 1;
+    }
+
+    fn merge_state(&mut self, other: &dyn std::any::Any) {
+        let other = other
+            .downcast_ref::<Self>()
+            .expect("Length can only be merged with Length");
+
+        self.nb_records += other.nb_records;
+        self.total += other.total;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        for (length, count) in other.histogram.iter() {
+            *self.histogram.entry(*length).or_insert(0) += count;
+        }
+    }
+
+    /// Length of the median record
+    fn median(&self) -> u64 {
+        let target = (self.nb_records + 1) / 2;
+
+        let mut cumulative = 0;
+        for (length, count) in self.histogram.iter() {
+            cumulative += count;
+            if cumulative >= target {
+                return *length;
+            }
+        }
+
+        0
+    }
+
+    /// Compute Nx and Lx, length of shortest record and number of record needed to cover ratio
+    /// of total length, when records are sorted by decreasing length
+    fn nx_lx(&self, ratio: f64) -> (u64, u64) {
+        let total: u64 = self.histogram.iter().map(|(l, c)| l * c).sum();
+        let threshold = ratio * total as f64;
+
+        let mut cumulative = 0;
+        let mut nb_records = 0;
+        for (length, count) in self.histogram.iter().rev() {
+            if *length == 0 {
+                break;
+            }
+
+            let missing = threshold - cumulative as f64;
+            if ((length * count) as f64) >= missing {
+                let needed = (missing / *length as f64).ceil().max(1.0) as u64;
+
+                return (*length, nb_records + needed);
+            }
+
+            cumulative += length * count;
+            nb_records += count;
+        }
+
+        (0, 0)
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let (n50, l50) = self.nx_lx(0.5);
+        let (n90, _) = self.nx_lx(0.9);
+
+        vec![
+            (
+                "mean_length".to_string(),
+                Value::Rounded(self.total as f64 / self.nb_records as f64),
+            ),
+            ("n".to_string(), Value::Integer(self.nb_records)),
+            ("yield".to_string(), Value::Integer(self.total)),
+            (
+                "min_length".to_string(),
+                Value::Integer(if self.nb_records == 0 { 0 } else { self.min }),
+            ),
+            ("max_length".to_string(), Value::Integer(self.max)),
+            ("median_length".to_string(), Value::Integer(self.median())),
+            ("n50".to_string(), Value::Integer(n50)),
+            ("n90".to_string(), Value::Integer(n90)),
+            ("l50".to_string(), Value::Integer(l50)),
+            (
+                "length_histogram".to_string(),
+                Value::List(
+                    self.histogram
+                        .iter()
+                        .map(|(l, c)| Value::List(vec![Value::Integer(*l), Value::Integer(*c)]))
+                        .collect(),
+                ),
+            ),
+        ]
+    }
+}
+
+impl Default for Length {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metric<fastq::Record> for Length {
+    fn empty(&self) -> Box<dyn Metric<fastq::Record>> {
+        Box::new(Self::new())
+    }
+
+    fn observe(&mut self, record: &fastq::Record) {
+        self.add(record.sequence().len())
+    }
+
+    fn merge(&mut self, other: &dyn Metric<fastq::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl Metric<noodles::bam::Record> for Length {
+    fn empty(&self) -> Box<dyn Metric<noodles::bam::Record>> {
+        Box::new(Self::new())
+    }
+
+    fn observe(&mut self, record: &noodles::bam::Record) {
+        self.add(record.sequence().len())
+    }
+
+    fn merge(&mut self, other: &dyn Metric<noodles::bam::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn bin_() {
+        assert_eq!(bin(0), 0);
+        assert_eq!(bin(150), 150);
+        assert_eq!(bin(EXACT_LIMIT - 1), EXACT_LIMIT - 1);
+        assert_eq!(bin(EXACT_LIMIT + 31), EXACT_LIMIT);
+        assert_eq!(bin(EXACT_LIMIT + 32), EXACT_LIMIT + 32);
+        assert_eq!(bin(1_000_123), 999_936);
+    }
+
+    #[test]
+    fn fastq() {
+        let mut metric = Length::new();
+
+        Metric::<fastq::Record>::observe(
+            &mut metric,
+            &fastq::Record::new(b"ACTGN".to_vec(), b"!!!!!".to_vec()),
+        );
+        Metric::<fastq::Record>::observe(
+            &mut metric,
+            &fastq::Record::new(b"ACT".to_vec(), b"!!!".to_vec()),
+        );
+
+        assert_eq!(
+            Metric::<fastq::Record>::finalize(&metric)[..9],
+            vec![
+                ("mean_length".to_string(), Value::Rounded(4.0)),
+                ("n".to_string(), Value::Integer(2)),
+                ("yield".to_string(), Value::Integer(8)),
+                ("min_length".to_string(), Value::Integer(3)),
+                ("max_length".to_string(), Value::Integer(5)),
+                ("median_length".to_string(), Value::Integer(3)),
+                ("n50".to_string(), Value::Integer(5)),
+                ("n90".to_string(), Value::Integer(3)),
+                ("l50".to_string(), Value::Integer(1)),
+            ][..]
+        );
+    }
+
+    #[test]
+    fn nx_lx() {
+        let mut metric = Length::new();
+        for length in [2, 3, 4, 5, 6, 7, 8, 9, 10, 10] {
+            metric.add(length);
+        }
+
+        // total 64, half 32 reach with 10 + 10 + 9 + 8
+        assert_eq!(metric.nx_lx(0.5), (8, 4));
+        // 90% 57.6 reach with 10 + 10 + 9 + 8 + 7 + 6 + 5 + 4
+        assert_eq!(metric.nx_lx(0.9), (4, 8));
+        assert_eq!(metric.median(), 6);
+
+        let fields = metric.fields();
+        assert_eq!(
+            fields[9],
+            (
+                "length_histogram".to_string(),
+                Value::List(
+                    [2, 3, 4, 5, 6, 7, 8, 9]
+                        .iter()
+                        .map(|l| Value::List(vec![Value::Integer(*l), Value::Integer(1)]))
+                        .chain(std::iter::once(Value::List(vec![
+                            Value::Integer(10),
+                            Value::Integer(2)
+                        ])))
+                        .collect()
+                )
+            )
+        );
+
+        let empty = Length::new().fields();
+        assert_eq!(empty[3], ("min_length".to_string(), Value::Integer(0)));
+        assert_eq!(empty[6], ("n50".to_string(), Value::Integer(0)));
+    }
+
+    #[test]
+    fn bam() {
+        let record = noodles::bam::Record::try_from_sam_record(
+            noodles::sam::Header::builder()
+                .build()
+                .reference_sequences(),
+            &noodles::sam::Record::builder()
+                .set_read_name("1".parse().unwrap())
+                .set_flags(noodles::sam::record::Flags::UNMAPPED)
+                .set_sequence("ACTGN".parse().unwrap())
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let mut metric = Length::new();
+        Metric::<noodles::bam::Record>::observe(&mut metric, &record);
+
+        let mut other = Length::new();
+        Metric::<noodles::bam::Record>::observe(&mut other, &record);
+
+        Metric::<noodles::bam::Record>::merge(&mut metric, &other);
+
+        assert_eq!(metric.nb_records, 2);
+        assert_eq!(metric.total, 10);
+        assert_eq!(metric.histogram.get(&5), Some(&2));
+    }
+}
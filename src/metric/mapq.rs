@@ -0,0 +1,162 @@
+//! Mapping quality of aligned records
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+use crate::metric::{Field, Metric, Value};
+
+/// Number of possible mapping quality, 255 mean mapping quality is unavailable
+pub const NB_MAPQ: usize = 256;
+
+/// Mapping quality value when it's unavailable
+const MISSING: usize = 255;
+
+/// Histogram of mapping quality of primary mapped records
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mapq {
+    histogram: Vec<u64>,
+}
+
+impl Mapq {
+    /// Create an empty Mapq
+    pub fn new() -> Self {
+        Self {
+            histogram: vec![0; NB_MAPQ],
+        }
+    }
+
+    fn merge_state(&mut self, other: &dyn std::any::Any) {
+        let other = other
+            .downcast_ref::<Self>()
+            .expect("Mapq can only be merged with Mapq");
+
+        self.histogram
+            .iter_mut()
+            .zip(other.histogram.iter())
+            .for_each(|(a, b)| *a += b);
+    }
+
+    /// Percentage of records with a mapping quality greater or equal to threshold, unavailable
+    /// mapping quality isn't count
+    fn above(&self, threshold: usize) -> f64 {
+        let total: u64 = self.histogram.iter().sum();
+        let above: u64 = self.histogram[threshold..MISSING].iter().sum();
+
+        above as f64 / total as f64 * 100.0
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let total: u64 = self.histogram.iter().sum();
+
+        vec![
+            (
+                "mapq_0".to_string(),
+                Value::Float(self.histogram[0] as f64 / total as f64 * 100.0),
+            ),
+            ("mapq_20".to_string(), Value::Float(self.above(20))),
+            ("mapq_30".to_string(), Value::Float(self.above(30))),
+            (
+                "mapq_histogram".to_string(),
+                Value::List(self.histogram.iter().map(|c| Value::Integer(*c)).collect()),
+            ),
+        ]
+    }
+}
+
+impl Default for Mapq {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metric<noodles::bam::Record> for Mapq {
+    fn empty(&self) -> Box<dyn Metric<noodles::bam::Record>> {
+        Box::new(Self::new())
+    }
+
+    fn observe(&mut self, record: &noodles::bam::Record) {
+        let flags = record.flags();
+
+        if flags.is_unmapped() || flags.is_secondary() || flags.is_supplementary() {
+            return;
+        }
+
+        let mapq = record
+            .mapping_quality()
+            .map(|mapq| u8::from(mapq) as usize)
+            .unwrap_or(MISSING);
+
+        self.histogram[mapq] += 1;
+    }
+
+    fn merge(&mut self, other: &dyn Metric<noodles::bam::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use noodles::sam::record::Flags;
+
+    fn record(flags: Flags, mapq: u8) -> noodles::bam::Record {
+        let mut record = noodles::bam::Record::default();
+
+        *record.flags_mut() = flags;
+        *record.mapping_quality_mut() = mapq.try_into().ok();
+
+        record
+    }
+
+    #[test]
+    fn mapq() {
+        let mut metric = Mapq::new();
+        for record in [
+            record(Flags::empty(), 0),
+            record(Flags::empty(), 0),
+            record(Flags::empty(), 20),
+            record(Flags::empty(), 60),
+            record(Flags::empty(), 255),
+            record(Flags::UNMAPPED, 0),
+            record(Flags::SECONDARY, 0),
+            record(Flags::SUPPLEMENTARY, 0),
+        ] {
+            metric.observe(&record);
+        }
+
+        let mut other = Mapq::new();
+        other.observe(&record(Flags::empty(), 25));
+        metric.merge(&other);
+
+        let fields = metric.finalize();
+
+        assert_eq!(
+            fields[..3],
+            vec![
+                ("mapq_0".to_string(), Value::Float(2.0 / 6.0 * 100.0)),
+                ("mapq_20".to_string(), Value::Float(3.0 / 6.0 * 100.0)),
+                ("mapq_30".to_string(), Value::Float(1.0 / 6.0 * 100.0)),
+            ][..]
+        );
+
+        if let Value::List(histogram) = &fields[3].1 {
+            assert_eq!(histogram.len(), NB_MAPQ);
+            assert_eq!(histogram[0], Value::Integer(2));
+            assert_eq!(histogram[25], Value::Integer(1));
+            assert_eq!(histogram[255], Value::Integer(1));
+        } else {
+            panic!("mapq_histogram isn't a list");
+        }
+    }
+}
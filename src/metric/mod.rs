@@ -0,0 +1,511 @@
+//! Metric computed on records
+//!
+//! A metric observe records, partial states computed by each rayon thread are merged and finally
+//! converted in named output fields.
+
+/* mod declaration section */
+pub mod composition;
+pub mod coverage;
+pub mod flagstat;
+pub mod gc;
+pub mod idxstats;
+pub mod insert_size;
+pub mod length;
+pub mod mapq;
+pub mod position;
+pub mod quality;
+pub mod target;
+
+/* pub use section */
+pub use composition::Composition;
+pub use coverage::Coverage;
+pub use flagstat::Flagstat;
+pub use gc::Gc;
+pub use idxstats::Idxstats;
+pub use insert_size::InsertSize;
+pub use length::Length;
+pub use mapq::Mapq;
+pub use position::Position;
+pub use quality::Quality;
+pub use target::OnTarget;
+
+/* crates use */
+
+/* project use */
+use crate::input::batch;
+
+/// Value of an output field
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    /// An integer value
+    Integer(u64),
+
+    /// A float value
+    Float(f64),
+
+    /// A float value display with only 2 decimals
+    Rounded(f64),
+
+    /// A text value
+    Text(String),
+
+    /// A list of value, histogram, profile, ...
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// Return true if value isn't a List
+    pub fn is_scalar(&self) -> bool {
+        !matches!(self, Value::List(_))
+    }
+
+    /// Get value as f64, None for Text and List
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(v) => Some(*v as f64),
+            Value::Float(v) | Value::Rounded(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Integer(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{:.4}", v),
+            Value::Rounded(v) => write!(f, "{:.2}", v),
+            Value::Text(v) => write!(f, "{}", v),
+            Value::List(values) => {
+                write!(f, "[")?;
+                for (i, v) in values.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ";")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// A named output field
+pub type Field = (String, Value);
+
+/// Trait of all metrics, R is the type of record observed
+///
+/// Metrics can be implement outside of crazyqc and add in a [MetricSet].
+pub trait Metric<R>: Send + Sync {
+    /// Create a new metric with same configuration and an empty state
+    fn empty(&self) -> Box<dyn Metric<R>>;
+
+    /// Create a new metric with an empty state that observe only records of file index, by
+    /// default same as [Metric::empty]
+    fn empty_for_file(&self, _index: usize) -> Box<dyn Metric<R>> {
+        self.empty()
+    }
+
+    /// Update state with a record
+    fn observe(&mut self, record: &R);
+
+    /// Merge state of other in self, other is always a metric build by [Metric::empty] or
+    /// [Metric::empty_for_file] of self
+    fn merge(&mut self, other: &dyn Metric<R>);
+
+    /// Convert state in named output fields
+    fn finalize(&self) -> Vec<Field>;
+
+    /// Get metric as Any, required to downcast other in [Metric::merge]
+    fn as_any(&self) -> &dyn std::any::Any;
+}
+
+/// A set of metric
+pub struct MetricSet<R> {
+    metrics: Vec<Box<dyn Metric<R>>>,
+}
+
+impl<R> MetricSet<R> {
+    /// Create an empty MetricSet
+    pub fn new() -> Self {
+        Self {
+            metrics: Vec::new(),
+        }
+    }
+
+    /// Add a metric in set
+    pub fn with<M>(mut self, metric: M) -> Self
+    where
+        M: Metric<R> + 'static,
+    {
+        self.metrics.push(Box::new(metric));
+
+        self
+    }
+
+    /// Create a new MetricSet with same metrics and an empty state
+    pub fn empty(&self) -> Self {
+        Self {
+            metrics: self.metrics.iter().map(|m| m.empty()).collect(),
+        }
+    }
+
+    /// Create a new MetricSet with same metrics and an empty state, for records of file index
+    pub fn empty_for_file(&self, index: usize) -> Self {
+        Self {
+            metrics: self
+                .metrics
+                .iter()
+                .map(|m| m.empty_for_file(index))
+                .collect(),
+        }
+    }
+
+    /// Update all metrics with a record
+    pub fn observe(&mut self, record: &R) {
+        self.metrics.iter_mut().for_each(|m| m.observe(record));
+    }
+
+    /// Merge state of other in self, other must be build by [MetricSet::empty] or
+    /// [MetricSet::empty_for_file] of self
+    pub fn merge(&mut self, other: &Self) {
+        self.metrics
+            .iter_mut()
+            .zip(other.metrics.iter())
+            .for_each(|(m, o)| m.merge(o.as_ref()));
+    }
+
+    /// Get fields of all metrics
+    pub fn finalize(&self) -> Vec<Field> {
+        self.metrics.iter().flat_map(|m| m.finalize()).collect()
+    }
+}
+
+impl<R> Default for MetricSet<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Number of batches by rayon thread, at most BATCHES_BY_THREAD * threads batches exist
+const BATCHES_BY_THREAD: usize = 2;
+
+/// Compute metrics of template on each record of reader, one MetricSet is return by input file
+///
+/// Batches are fill by calling thread and each batch is process by a task of rayon thread pool,
+/// so rayon threads never wait reader and are free to decompress input. A task accumulate records
+/// in MetricSets not use by other tasks. Processed batches are give back to reader to reuse
+/// records memory, number of batches is fixed so memory use by records is bounded. Parsing error
+/// are put in log.
+///
+/// If it's call in a rayon thread pool, pool must have more than one thread.
+pub fn compute<R, B>(reader: B, template: &MetricSet<R>, nb_files: usize) -> Vec<MetricSet<R>>
+where
+    R: Default + Send,
+    B: batch::Reader<Record = R>,
+{
+    let identity = || -> Vec<MetricSet<R>> {
+        (0..nb_files)
+            .map(|index| template.empty_for_file(index))
+            .collect()
+    };
+
+    let nb_batches = rayon::current_num_threads() * BATCHES_BY_THREAD;
+
+    let (free_sender, free_receiver) = std::sync::mpsc::channel();
+    for _ in 0..nb_batches {
+        free_sender.send(batch::Batch::default()).unwrap();
+    }
+
+    // MetricSets not use by a task
+    let idle = std::sync::Mutex::new(Vec::new());
+    let failled = std::sync::atomic::AtomicBool::new(false);
+
+    rayon::in_place_scope(|scope| {
+        read_batches(reader, &free_receiver, &failled, |batch| {
+            let mut giver = Giver {
+                batch: Some(batch),
+                free: free_sender.clone(),
+                failled: &failled,
+            };
+            let (idle, identity) = (&idle, &identity);
+
+            scope.spawn(move |_| {
+                let batch = giver.batch.as_mut().unwrap();
+                let mut sets = idle.lock().unwrap().pop().unwrap_or_else(identity);
+
+                batch
+                    .take_errors()
+                    .iter()
+                    .for_each(|e| e.chain().for_each(|b| log::error!("{}", b)));
+
+                let set = &mut sets[batch.index()];
+                batch
+                    .records()
+                    .iter()
+                    .for_each(|record| set.observe(record));
+
+                idle.lock().unwrap().push(sets);
+            });
+        });
+    });
+
+    idle.into_inner()
+        .unwrap()
+        .into_iter()
+        .fold(identity(), |mut a, b| {
+            a.iter_mut().zip(b.iter()).for_each(|(x, y)| x.merge(y));
+            a
+        })
+}
+
+/// Give back a batch to reader when a task end, if task panic reading is stop
+struct Giver<'a, R> {
+    batch: Option<batch::Batch<R>>,
+    free: std::sync::mpsc::Sender<batch::Batch<R>>,
+    failled: &'a std::sync::atomic::AtomicBool,
+}
+
+impl<'a, R> Drop for Giver<'a, R> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.failled
+                .store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        if let Some(batch) = self.batch.take() {
+            // reader could be end, batch is just drop
+            let _ = self.free.send(batch);
+        }
+    }
+}
+
+/// Fill free batches with reader and give them to process, wait a batch give back when all
+/// batches are in use, stop if failled is set
+fn read_batches<R, B, F>(
+    mut reader: B,
+    free: &std::sync::mpsc::Receiver<batch::Batch<R>>,
+    failled: &std::sync::atomic::AtomicBool,
+    mut process: F,
+) where
+    R: Default,
+    B: batch::Reader<Record = R>,
+    F: FnMut(batch::Batch<R>),
+{
+    while let Ok(mut batch) = free.recv() {
+        if failled.load(std::sync::atomic::Ordering::SeqCst) || !reader.fill(&mut batch) {
+            break;
+        }
+
+        process(batch);
+    }
+}
+
+/// Merge all MetricSet in one, sets must be build by [MetricSet::empty] of template
+pub fn aggregate<R>(sets: &[MetricSet<R>], template: &MetricSet<R>) -> MetricSet<R> {
+    let mut all = template.empty();
+
+    sets.iter().for_each(|s| all.merge(s));
+
+    all
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use crate::input::fastq;
+
+    fn record(seq: &[u8]) -> fastq::Record {
+        fastq::Record::new(seq.to_vec(), vec![b'I'; seq.len()])
+    }
+
+    fn template() -> MetricSet<fastq::Record> {
+        MetricSet::new()
+            .with(Composition::new())
+            .with(Length::new())
+    }
+
+    #[test]
+    fn value_display() {
+        assert_eq!(format!("{}", Value::Integer(3)), "3");
+        assert_eq!(format!("{}", Value::Float(0.5)), "0.5000");
+        assert_eq!(format!("{}", Value::Rounded(10.0 / 3.0)), "3.33");
+        assert_eq!(format!("{}", Value::Text("a".to_string())), "a");
+        assert_eq!(
+            format!(
+                "{}",
+                Value::List(vec![Value::Integer(1), Value::Integer(2)])
+            ),
+            "[1;2]"
+        );
+
+        assert_eq!(Value::Integer(3).as_f64(), Some(3.0));
+        assert_eq!(Value::Float(0.5).as_f64(), Some(0.5));
+        assert_eq!(Value::Rounded(0.5).as_f64(), Some(0.5));
+        assert_eq!(Value::Text("a".to_string()).as_f64(), None);
+
+        assert!(Value::Integer(3).is_scalar());
+        assert!(!Value::List(vec![]).is_scalar());
+    }
+
+    #[test]
+    fn metric_set() {
+        let mut set = template().empty();
+        set.observe(&record(b"ACTG"));
+
+        let mut other = template().empty();
+        other.observe(&record(b"ACGN"));
+
+        set.merge(&other);
+
+        let fields = set.finalize();
+        assert_eq!(
+            fields[..3],
+            vec![
+                ("at".to_string(), Value::Float(37.5)),
+                ("gc".to_string(), Value::Float(50.0)),
+                ("other".to_string(), Value::Float(12.5)),
+            ][..]
+        );
+        assert_eq!(
+            fields[10..12],
+            vec![
+                ("mean_length".to_string(), Value::Rounded(4.0)),
+                ("n".to_string(), Value::Integer(2)),
+            ][..]
+        );
+    }
+
+    #[test]
+    fn compute_and_aggregate() {
+        let records = vec![
+            (0, Ok(record(b"ACTG"))),
+            (1, Ok(record(b"AAAA"))),
+            (1, Err(anyhow::anyhow!("prout"))),
+            (0, Ok(record(b"GG"))),
+        ];
+
+        let template = template();
+        let sets = compute(batch::Batched::new(records.into_iter()), &template, 2);
+
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[0].finalize()[11], ("n".to_string(), Value::Integer(2)));
+        assert_eq!(
+            sets[1].finalize()[0],
+            ("at".to_string(), Value::Float(100.0))
+        );
+
+        let all = aggregate(&sets, &template);
+        assert_eq!(
+            all.finalize()[10],
+            ("mean_length".to_string(), Value::Rounded(10.0 / 3.0))
+        );
+        assert_eq!(all.finalize()[11], ("n".to_string(), Value::Integer(3)));
+    }
+
+    #[test]
+    fn read_batches_bounded() {
+        let records = std::iter::repeat_with(|| (0, Ok(record(b"ACGT"))));
+
+        let (full_sender, full_receiver) = std::sync::mpsc::channel();
+        let (free_sender, free_receiver) = std::sync::mpsc::channel();
+        for _ in 0..2 {
+            free_sender.send(batch::Batch::default()).unwrap();
+        }
+        let producer = std::thread::spawn(move || {
+            let failled = std::sync::atomic::AtomicBool::new(false);
+            read_batches(
+                batch::Batched::new(records),
+                &free_receiver,
+                &failled,
+                |batch| full_sender.send(batch).unwrap(),
+            )
+        });
+
+        let first = full_receiver.recv().unwrap();
+        full_receiver.recv().unwrap();
+
+        // all batches are in use, reader wait
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(full_receiver.try_recv().is_err());
+
+        free_sender.send(first).unwrap();
+        assert_eq!(
+            full_receiver.recv().unwrap().records().len(),
+            batch::BATCHED_RECORDS
+        );
+
+        drop(free_sender);
+        producer.join().unwrap();
+    }
+
+    #[test]
+    fn compute_panic() {
+        struct Panic;
+
+        impl Metric<fastq::Record> for Panic {
+            fn empty(&self) -> Box<dyn Metric<fastq::Record>> {
+                Box::new(Panic)
+            }
+
+            fn observe(&mut self, _record: &fastq::Record) {
+                panic!("metric failled");
+            }
+
+            fn merge(&mut self, _other: &dyn Metric<fastq::Record>) {}
+
+            fn finalize(&self) -> Vec<Field> {
+                Vec::new()
+            }
+
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+        }
+
+        // reading stop and panic is propagate, even with an infinite reader
+        let records = std::iter::repeat_with(|| (0, Ok(record(b"ACGT"))));
+        let template = MetricSet::new().with(Panic);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            compute(batch::Batched::new(records), &template, 1)
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn compute_bgzf_many_threads() {
+        use std::io::Write;
+
+        let tmp_file = tempfile::NamedTempFile::new().unwrap();
+        {
+            let mut writer = noodles::bgzf::Writer::new(tmp_file.reopen().unwrap());
+            for i in 0..500_000 {
+                writeln!(writer, "@{}\nACGTACGTACGTACGT\n+\nIIIIIIIIIIIIIIII", i).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+        let path = tmp_file.path().to_str().unwrap().to_string();
+
+        // reader and metrics must not wait on each other in rayon thread pool
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(16)
+                .build()
+                .unwrap();
+            let fields = pool.install(|| {
+                let reader = fastq::Fastq::new(vec![path], 8192).unwrap();
+                compute(reader, &template(), 1)[0].finalize()
+            });
+            sender.send(fields).unwrap();
+        });
+
+        let fields = receiver
+            .recv_timeout(std::time::Duration::from_secs(120))
+            .expect("compute on bgzf input with many threads doesn't finish");
+        assert_eq!(fields[11], ("n".to_string(), Value::Integer(500_000)));
+    }
+}
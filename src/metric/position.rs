@@ -0,0 +1,362 @@
+//! Per position (cycle) quality and base composition profile
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+use crate::input::fastq;
+use crate::metric::quality::{NB_SCORES, PHRED_OFFSET};
+use crate::metric::{Field, Metric, Value};
+
+/// Name of base tracked by profile, other base are count as N
+const BASES: [&str; 5] = ["a", "c", "g", "t", "n"];
+
+/// Convert a nucleotide in index of [BASES]
+fn nuc2index(nuc: u8) -> usize {
+    match nuc {
+        b'a' | b'A' => 0,
+        b'c' | b'C' => 1,
+        b'g' | b'G' => 2,
+        b't' | b'T' => 3,
+        _ => 4,
+    }
+}
+
+/// Convert a bam base in index of [BASES]
+fn base2index(base: noodles::bam::record::sequence::Base) -> usize {
+    match base {
+        noodles::bam::record::sequence::Base::A => 0,
+        noodles::bam::record::sequence::Base::C => 1,
+        noodles::bam::record::sequence::Base::G => 2,
+        noodles::bam::record::sequence::Base::T => 3,
+        _ => 4,
+    }
+}
+
+/// Get the index of complement of a base index
+fn complement(index: usize) -> usize {
+    if index < 4 {
+        3 - index
+    } else {
+        index
+    }
+}
+
+/// Smallest score where cumulative count reach `ratio` of total
+fn percentile(histogram: &[u64; NB_SCORES], ratio: f64) -> Value {
+    let total: u64 = histogram.iter().sum();
+    if total == 0 {
+        return Value::Float(f64::NAN);
+    }
+
+    let mut cumulative = 0;
+    for (score, count) in histogram.iter().enumerate() {
+        cumulative += count;
+        if cumulative as f64 >= ratio * total as f64 {
+            return Value::Integer(score as u64);
+        }
+    }
+
+    Value::Integer((NB_SCORES - 1) as u64)
+}
+
+/// Quality histogram and base count at each position of reads
+///
+/// Only the first `max_length` positions are tracked, storage grow with the longest read seen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    max_length: usize,
+    quality: Vec<[u64; NB_SCORES]>,
+    bases: Vec<[u64; 5]>,
+}
+
+impl Position {
+    /// Create an empty Position, with maximal number of tracked position
+    pub fn new(max_length: usize) -> Self {
+        Self {
+            max_length,
+            quality: Vec::new(),
+            bases: Vec::new(),
+        }
+    }
+
+    /// Grow storage to track length positions, return number of position tracked
+    fn reserve(&mut self, length: usize) -> usize {
+        let length = length.min(self.max_length);
+
+        if self.bases.len() < length {
+            self.bases.resize(length, [0; 5]);
+            self.quality.resize(length, [0; NB_SCORES]);
+        }
+
+        length
+    }
+
+    fn merge_state(&mut self, other: &dyn std::any::Any) {
+        let other = other
+            .downcast_ref::<Self>()
+            .expect("Position can only be merged with Position");
+
+        self.reserve(other.bases.len());
+
+        self.bases
+            .iter_mut()
+            .zip(other.bases.iter())
+            .for_each(|(a, b)| a.iter_mut().zip(b.iter()).for_each(|(x, y)| *x += y));
+        self.quality
+            .iter_mut()
+            .zip(other.quality.iter())
+            .for_each(|(a, b)| a.iter_mut().zip(b.iter()).for_each(|(x, y)| *x += y));
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let mut fields = vec![
+            (
+                "position_mean_quality".to_string(),
+                Value::List(
+                    self.quality
+                        .iter()
+                        .map(|h| {
+                            let total: u64 = h.iter().sum();
+                            let sum: u64 = h
+                                .iter()
+                                .enumerate()
+                                .map(|(score, count)| score as u64 * count)
+                                .sum();
+
+                            Value::Float(sum as f64 / total as f64)
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "position_q1_quality".to_string(),
+                Value::List(self.quality.iter().map(|h| percentile(h, 0.25)).collect()),
+            ),
+            (
+                "position_median_quality".to_string(),
+                Value::List(self.quality.iter().map(|h| percentile(h, 0.5)).collect()),
+            ),
+            (
+                "position_q3_quality".to_string(),
+                Value::List(self.quality.iter().map(|h| percentile(h, 0.75)).collect()),
+            ),
+        ];
+
+        for (index, name) in BASES.iter().enumerate() {
+            fields.push((
+                format!("position_{}", name),
+                Value::List(
+                    self.bases
+                        .iter()
+                        .map(|counts| {
+                            let total: u64 = counts.iter().sum();
+
+                            Value::Float(counts[index] as f64 / total as f64 * 100.0)
+                        })
+                        .collect(),
+                ),
+            ));
+        }
+
+        fields
+    }
+}
+
+impl Metric<fastq::Record> for Position {
+    fn empty(&self) -> Box<dyn Metric<fastq::Record>> {
+        Box::new(Self::new(self.max_length))
+    }
+
+    fn observe(&mut self, record: &fastq::Record) {
+        let length = self.reserve(record.sequence().len());
+
+        for (pos, nuc) in record.sequence()[..length].iter().enumerate() {
+            self.bases[pos][nuc2index(*nuc)] += 1;
+        }
+
+        for (pos, q) in record.quality_scores().iter().take(length).enumerate() {
+            let score = (q.saturating_sub(PHRED_OFFSET) as usize).min(NB_SCORES - 1);
+
+            self.quality[pos][score] += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &dyn Metric<fastq::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl Metric<noodles::bam::Record> for Position {
+    fn empty(&self) -> Box<dyn Metric<noodles::bam::Record>> {
+        Box::new(Self::new(self.max_length))
+    }
+
+    fn observe(&mut self, record: &noodles::bam::Record) {
+        let read_length = record.sequence().len();
+        let length = self.reserve(read_length);
+        let reverse = record.flags().is_reverse_complemented();
+
+        // Reverse complemented record are store in reference orientation, cycle is count from end
+        let cycle = |i: usize| if reverse { read_length - 1 - i } else { i };
+
+        for (i, base) in record.sequence().bases().enumerate() {
+            let pos = cycle(i);
+            if pos < length {
+                let index = base2index(base);
+                self.bases[pos][if reverse { complement(index) } else { index }] += 1;
+            }
+        }
+
+        // Missing quality is encoded by 0xFF
+        let scores = record.quality_scores().as_ref();
+        if scores.first().map_or(true, |q| *q == 0xFF) {
+            return;
+        }
+
+        for (i, q) in scores.iter().enumerate() {
+            let pos = cycle(i);
+            if pos < length {
+                self.quality[pos][(*q as usize).min(NB_SCORES - 1)] += 1;
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &dyn Metric<noodles::bam::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    fn get_list(fields: &[Field], name: &str) -> Vec<Value> {
+        match fields.iter().find(|(n, _)| n == name) {
+            Some((_, Value::List(values))) => values.clone(),
+            _ => panic!("field {} isn't a list", name),
+        }
+    }
+
+    #[test]
+    fn fastq() {
+        let mut metric = Position::new(3);
+        metric.observe(&fastq::Record::new(b"ACTG".to_vec(), b"!+5?".to_vec()));
+
+        let mut other = Position::new(3);
+        other.observe(&fastq::Record::new(b"AG".to_vec(), b"II".to_vec()));
+
+        metric.merge_state(&other);
+
+        let fields = metric.fields();
+
+        assert_eq!(
+            get_list(&fields, "position_mean_quality"),
+            vec![Value::Float(20.0), Value::Float(25.0), Value::Float(20.0)]
+        );
+        assert_eq!(
+            get_list(&fields, "position_q1_quality"),
+            vec![Value::Integer(0), Value::Integer(10), Value::Integer(20)]
+        );
+        assert_eq!(
+            get_list(&fields, "position_median_quality"),
+            vec![Value::Integer(0), Value::Integer(10), Value::Integer(20)]
+        );
+        assert_eq!(
+            get_list(&fields, "position_q3_quality"),
+            vec![Value::Integer(40), Value::Integer(40), Value::Integer(20)]
+        );
+        assert_eq!(
+            get_list(&fields, "position_a"),
+            vec![Value::Float(100.0), Value::Float(0.0), Value::Float(0.0)]
+        );
+        assert_eq!(
+            get_list(&fields, "position_g"),
+            vec![Value::Float(0.0), Value::Float(50.0), Value::Float(0.0)]
+        );
+        assert_eq!(
+            get_list(&fields, "position_t"),
+            vec![Value::Float(0.0), Value::Float(0.0), Value::Float(100.0)]
+        );
+    }
+
+    #[test]
+    fn bam() {
+        let header = noodles::sam::Header::builder().build();
+        let forward = noodles::bam::Record::try_from_sam_record(
+            header.reference_sequences(),
+            &noodles::sam::Record::builder()
+                .set_read_name("1".parse().unwrap())
+                .set_flags(noodles::sam::record::Flags::UNMAPPED)
+                .set_sequence("ACGN".parse().unwrap())
+                .set_quality_scores("!+5?".parse().unwrap())
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        let reverse = noodles::bam::Record::try_from_sam_record(
+            header.reference_sequences(),
+            &noodles::sam::Record::builder()
+                .set_read_name("2".parse().unwrap())
+                .set_flags(
+                    noodles::sam::record::Flags::UNMAPPED
+                        | noodles::sam::record::Flags::REVERSE_COMPLEMENTED,
+                )
+                .set_sequence("AACG".parse().unwrap())
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let mut metric = Position::new(10);
+        metric.observe(&forward);
+        metric.observe(&reverse);
+
+        let fields = metric.fields();
+
+        assert_eq!(
+            get_list(&fields, "position_mean_quality"),
+            vec![
+                Value::Float(0.0),
+                Value::Float(10.0),
+                Value::Float(20.0),
+                Value::Float(30.0)
+            ]
+        );
+        assert_eq!(
+            get_list(&fields, "position_c"),
+            vec![
+                Value::Float(50.0),
+                Value::Float(50.0),
+                Value::Float(0.0),
+                Value::Float(0.0)
+            ]
+        );
+        assert_eq!(
+            get_list(&fields, "position_t"),
+            vec![
+                Value::Float(0.0),
+                Value::Float(0.0),
+                Value::Float(50.0),
+                Value::Float(50.0)
+            ]
+        );
+    }
+}
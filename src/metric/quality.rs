@@ -0,0 +1,139 @@
+//! Base quality of records
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+use crate::input::fastq;
+use crate::metric::{Field, Metric, Value};
+
+/// Offset of Phred score in fastq quality string
+pub const PHRED_OFFSET: u8 = 33;
+
+/// Number of possible Phred score in fastq quality string
+pub const NB_SCORES: usize = 94;
+
+/// Histogram of base quality Phred score
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quality {
+    histogram: Vec<u64>,
+}
+
+impl Quality {
+    /// Create an empty Quality
+    pub fn new() -> Self {
+        Self {
+            histogram: vec![0; NB_SCORES],
+        }
+    }
+
+    fn merge_state(&mut self, other: &dyn std::any::Any) {
+        let other = other
+            .downcast_ref::<Self>()
+            .expect("Quality can only be merged with Quality");
+
+        self.histogram
+            .iter_mut()
+            .zip(other.histogram.iter())
+            .for_each(|(a, b)| *a += b);
+    }
+
+    /// Percentage of bases with a score greater or equal to threshold
+    fn above(&self, threshold: usize) -> f64 {
+        let total: u64 = self.histogram.iter().sum();
+        let above: u64 = self.histogram[threshold..].iter().sum();
+
+        above as f64 / total as f64 * 100.0
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let total: u64 = self.histogram.iter().sum();
+        let sum: u64 = self
+            .histogram
+            .iter()
+            .enumerate()
+            .map(|(score, count)| score as u64 * count)
+            .sum();
+
+        vec![
+            (
+                "mean_quality".to_string(),
+                Value::Float(sum as f64 / total as f64),
+            ),
+            ("q20".to_string(), Value::Float(self.above(20))),
+            ("q30".to_string(), Value::Float(self.above(30))),
+            ("q40".to_string(), Value::Float(self.above(40))),
+            (
+                "quality_histogram".to_string(),
+                Value::List(self.histogram.iter().map(|c| Value::Integer(*c)).collect()),
+            ),
+        ]
+    }
+}
+
+impl Default for Quality {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metric<fastq::Record> for Quality {
+    fn empty(&self) -> Box<dyn Metric<fastq::Record>> {
+        Box::new(Self::new())
+    }
+
+    fn observe(&mut self, record: &fastq::Record) {
+        for q in record.quality_scores() {
+            let score = (q.saturating_sub(PHRED_OFFSET) as usize).min(NB_SCORES - 1);
+
+            self.histogram[score] += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &dyn Metric<fastq::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn fastq() {
+        let mut metric = Quality::new();
+        metric.observe(&fastq::Record::new(b"ACTG".to_vec(), b"!+5?".to_vec()));
+
+        let mut other = Quality::new();
+        other.observe(&fastq::Record::new(b"ACTG".to_vec(), b"IIII".to_vec()));
+
+        metric.merge(&other);
+
+        let fields = metric.finalize();
+
+        assert_eq!(fields[0], ("mean_quality".to_string(), Value::Float(27.5)));
+        assert_eq!(fields[1], ("q20".to_string(), Value::Float(75.0)));
+        assert_eq!(fields[2], ("q30".to_string(), Value::Float(62.5)));
+        assert_eq!(fields[3], ("q40".to_string(), Value::Float(50.0)));
+
+        if let Value::List(histogram) = &fields[4].1 {
+            assert_eq!(histogram.len(), NB_SCORES);
+            assert_eq!(histogram[0], Value::Integer(1));
+            assert_eq!(histogram[10], Value::Integer(1));
+            assert_eq!(histogram[20], Value::Integer(1));
+            assert_eq!(histogram[30], Value::Integer(1));
+            assert_eq!(histogram[40], Value::Integer(4));
+        } else {
+            panic!("quality_histogram isn't a list");
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Classify alignments as on, near or off target regions
+
+/* std use */
+use std::sync::Arc;
+
+/* crates use */
+
+/* project use */
+use crate::input::bed;
+use crate::input::dictionary;
+use crate::metric::{Field, Metric, Value};
+
+/// Maximal distance between an alignment and a target to be near target, same value as Picard
+pub const NEAR_DISTANCE: u64 = 250;
+
+/// Count primary mapped reads on, near and off targets, QC-fail and duplicate reads are ignored
+#[derive(Debug, Clone)]
+pub struct OnTarget {
+    targets: Arc<bed::Targets>,
+    dictionaries: dictionary::Dictionaries,
+    file: Option<usize>,
+    chroms: Option<Vec<Option<usize>>>,
+    counts: [u64; 3],
+}
+
+impl OnTarget {
+    /// Create an empty OnTarget, names of reference sequences are found in dictionaries
+    pub fn new(targets: Arc<bed::Targets>, dictionaries: dictionary::Dictionaries) -> Self {
+        Self {
+            targets,
+            dictionaries,
+            file: None,
+            chroms: None,
+            counts: [0; 3],
+        }
+    }
+
+    /// Get targets of reference sequence id, mapping between id and targets is build at first
+    /// call
+    fn chrom(&mut self, id: usize) -> Option<&bed::ChromTargets> {
+        if self.chroms.is_none() {
+            let targets = &self.targets;
+            self.chroms = Some(
+                self.file
+                    .and_then(|i| self.dictionaries.get(i))
+                    .map(|dictionary| {
+                        dictionary
+                            .iter()
+                            .map(|(name, _)| targets.chrom_index(name))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            );
+        }
+
+        let chrom = (*self.chroms.as_ref()?.get(id)?)?;
+        Some(&self.targets.chroms()[chrom])
+    }
+
+    fn merge_state(&mut self, other: &dyn std::any::Any) {
+        let other = other
+            .downcast_ref::<Self>()
+            .expect("OnTarget can only be merged with OnTarget");
+
+        self.counts
+            .iter_mut()
+            .zip(other.counts.iter())
+            .for_each(|(a, b)| *a += b);
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        let total: u64 = self.counts.iter().sum();
+
+        vec![
+            (
+                "target_on_reads".to_string(),
+                Value::Integer(self.counts[0]),
+            ),
+            (
+                "target_near_reads".to_string(),
+                Value::Integer(self.counts[1]),
+            ),
+            (
+                "target_off_reads".to_string(),
+                Value::Integer(self.counts[2]),
+            ),
+            (
+                "target_on_percent".to_string(),
+                Value::Float(self.counts[0] as f64 / total as f64 * 100.0),
+            ),
+        ]
+    }
+}
+
+impl Metric<noodles::bam::Record> for OnTarget {
+    fn empty(&self) -> Box<dyn Metric<noodles::bam::Record>> {
+        Box::new(Self::new(self.targets.clone(), self.dictionaries.clone()))
+    }
+
+    fn empty_for_file(&self, index: usize) -> Box<dyn Metric<noodles::bam::Record>> {
+        let mut metric = Self::new(self.targets.clone(), self.dictionaries.clone());
+        metric.file = Some(index);
+
+        Box::new(metric)
+    }
+
+    fn observe(&mut self, record: &noodles::bam::Record) {
+        let flags = record.flags();
+        if flags.is_unmapped()
+            || flags.is_secondary()
+            || flags.is_supplementary()
+            || flags.is_qc_fail()
+            || flags.is_duplicate()
+        {
+            return;
+        }
+
+        let (id, start) = match (record.reference_sequence_id(), record.position()) {
+            (Some(id), Some(position)) => (i32::from(id) as usize, i32::from(position) as u64 - 1),
+            _ => return,
+        };
+        let end = start + record.cigar().reference_len().unwrap_or(0).max(1) as u64;
+
+        let class = match self.chrom(id) {
+            Some(chrom) if chrom.overlaps(start, end) => 0,
+            Some(chrom) if chrom.distance(start, end) <= NEAR_DISTANCE => 1,
+            _ => 2,
+        };
+
+        self.counts[class] += 1;
+    }
+
+    fn merge(&mut self, other: &dyn Metric<noodles::bam::Record>) {
+        self.merge_state(other.as_any())
+    }
+
+    fn finalize(&self) -> Vec<Field> {
+        self.fields()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use noodles::sam::record::Flags;
+
+    const NAMES: [&str; 2] = ["chr1", "chr2"];
+
+    fn record(flags: Flags, reference_id: usize, start: i32, cigar: &str) -> noodles::bam::Record {
+        let header = noodles::sam::Header::builder()
+            .add_reference_sequence(
+                noodles::sam::header::ReferenceSequence::new(NAMES[0].parse().unwrap(), 2000)
+                    .unwrap(),
+            )
+            .add_reference_sequence(
+                noodles::sam::header::ReferenceSequence::new(NAMES[1].parse().unwrap(), 2000)
+                    .unwrap(),
+            )
+            .build();
+
+        let record = noodles::sam::Record::builder()
+            .set_flags(flags)
+            .set_reference_sequence_name(NAMES[reference_id].parse().unwrap())
+            .set_position(start.try_into().unwrap())
+            .set_cigar(cigar.parse().unwrap())
+            .build()
+            .unwrap();
+
+        noodles::bam::Record::try_from_sam_record(header.reference_sequences(), &record).unwrap()
+    }
+
+    #[test]
+    fn on_target() {
+        let targets = bed::Targets::new(vec![bed::Target {
+            chrom: "chr1".to_string(),
+            start: 1000,
+            end: 1100,
+            name: "a".to_string(),
+        }]);
+        let dictionaries = dictionary::Dictionaries::new();
+        dictionaries.insert(
+            0,
+            vec![("chr1".to_string(), 2000), ("chr2".to_string(), 2000)],
+        );
+
+        let template = OnTarget::new(Arc::new(targets), dictionaries);
+
+        let mut metric = template.empty_for_file(0);
+        for record in [
+            record(Flags::empty(), 0, 992, "10M"),
+            record(Flags::empty(), 0, 1095, "10M"),
+            record(Flags::empty(), 0, 800, "10M"),
+            record(Flags::empty(), 0, 1300, "10M"),
+            record(Flags::empty(), 0, 1500, "10M"),
+            record(Flags::empty(), 1, 1000, "10M"),
+            record(Flags::DUPLICATE, 0, 1000, "10M"),
+            record(Flags::SECONDARY, 0, 1000, "10M"),
+        ] {
+            metric.observe(&record);
+        }
+
+        let mut all = template.empty();
+        all.merge(metric.as_ref());
+
+        assert_eq!(
+            all.finalize(),
+            vec![
+                ("target_on_reads".to_string(), Value::Integer(2)),
+                ("target_near_reads".to_string(), Value::Integer(2)),
+                ("target_off_reads".to_string(), Value::Integer(2)),
+                (
+                    "target_on_percent".to_string(),
+                    Value::Float(2.0 / 6.0 * 100.0)
+                ),
+            ]
+        );
+    }
+}
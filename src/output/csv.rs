@@ -0,0 +1,71 @@
+//! Write report in csv
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+use crate::report;
+
+/// Write fields of each input in csv, a field absent of an input is left empty
+pub fn write(output: &mut dyn std::io::Write, report: &report::Report) -> anyhow::Result<()> {
+    let names = report.field_names();
+
+    writeln!(output, "type,file,{}", names.join(","))?;
+
+    for input in report.inputs.iter() {
+        let values: Vec<String> = names
+            .iter()
+            .map(|name| input.get(name).map(|v| v.to_string()).unwrap_or_default())
+            .collect();
+
+        writeln!(
+            output,
+            "{},{},{}",
+            input.input_type,
+            input.file,
+            values.join(",")
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use crate::metric;
+
+    #[test]
+    fn write_() {
+        let mut report = report::Report::new();
+        report.inputs.push(report::Input {
+            input_type: "fastq".to_string(),
+            file: "a.fastq".to_string(),
+            metrics: vec![
+                ("at".to_string(), metric::Value::Float(50.0)),
+                ("n".to_string(), metric::Value::Integer(1)),
+            ],
+        });
+        report.inputs.push(report::Input {
+            input_type: "bam".to_string(),
+            file: "c.bam".to_string(),
+            metrics: vec![
+                ("n".to_string(), metric::Value::Integer(0)),
+                ("hist".to_string(), metric::Value::List(vec![])),
+            ],
+        });
+
+        let mut output = Vec::new();
+        write(&mut output, &report).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "type,file,at,n,hist
+fastq,a.fastq,50.0000,1,
+bam,c.bam,,0,[]
+"
+        );
+    }
+}
@@ -0,0 +1,379 @@
+//! Write report as a self-contained html file with svg plots
+
+/* std use */
+use std::fmt::Write as _;
+
+/* crates use */
+
+/* project use */
+use crate::metric;
+use crate::report;
+
+/// Width of plot in pixel
+const WIDTH: f64 = 720.0;
+
+/// Height of plot in pixel
+const HEIGHT: f64 = 360.0;
+
+/// Margin around plot area in pixel
+const MARGIN: f64 = 60.0;
+
+/// Color of series
+const COLORS: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+];
+
+/// A plot build from a list field
+struct Plot {
+    field: &'static str,
+    title: &'static str,
+    xlab: &'static str,
+    ylab: &'static str,
+    /// Value add to index of list to get x value, ignored for list of pair
+    x_offset: f64,
+}
+
+/// Plots write when field is present
+const PLOTS: [Plot; 3] = [
+    Plot {
+        field: "length_histogram",
+        title: "Read length distribution",
+        xlab: "Read length",
+        ylab: "Number of reads",
+        x_offset: 0.0,
+    },
+    Plot {
+        field: "gc_histogram",
+        title: "Per read GC content",
+        xlab: "GC %",
+        ylab: "Number of reads",
+        x_offset: 0.0,
+    },
+    Plot {
+        field: "position_mean_quality",
+        title: "Mean quality by position",
+        xlab: "Position",
+        ylab: "Mean Phred score",
+        x_offset: 1.0,
+    },
+];
+
+/// Escape html special characters
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Convert a list value in points, value that isn't a number are skip
+fn points(value: &metric::Value, x_offset: f64) -> Vec<(f64, f64)> {
+    let values = match value {
+        metric::Value::List(values) => values,
+        _ => return Vec::new(),
+    };
+
+    values
+        .iter()
+        .enumerate()
+        .filter_map(|(index, value)| match value {
+            metric::Value::List(pair) if pair.len() == 2 => {
+                Some((pair[0].as_f64()?, pair[1].as_f64()?))
+            }
+            _ => Some((index as f64 + x_offset, value.as_f64()?)),
+        })
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .collect()
+}
+
+/// Build a svg line chart, one line by series
+fn line_chart(plot: &Plot, series: &[(String, Vec<(f64, f64)>)]) -> String {
+    let all = series.iter().flat_map(|(_, p)| p.iter());
+    let (x_min, x_max, y_max) = all.fold(
+        (f64::INFINITY, f64::NEG_INFINITY, 0.0_f64),
+        |(x_min, x_max, y_max), (x, y)| (x_min.min(*x), x_max.max(*x), y_max.max(*y)),
+    );
+    let x_max = if x_max > x_min { x_max } else { x_min + 1.0 };
+    let y_max = if y_max > 0.0 { y_max } else { 1.0 };
+
+    let plot_width = WIDTH - 2.0 * MARGIN;
+    let plot_height = HEIGHT - 2.0 * MARGIN;
+    let x_pos = |x: f64| MARGIN + (x - x_min) / (x_max - x_min) * plot_width;
+    let y_pos = |y: f64| HEIGHT - MARGIN - y / y_max * plot_height;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = WIDTH,
+        h = HEIGHT
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="20" text-anchor="middle" font-weight="bold">{}</text>"#,
+        WIDTH / 2.0,
+        escape(plot.title)
+    );
+
+    /* Axis */
+    let _ = writeln!(
+        svg,
+        r#"<path d="M{l} {t} V{b} H{r}" fill="none" stroke="black"/>"#,
+        l = MARGIN,
+        t = MARGIN,
+        b = HEIGHT - MARGIN,
+        r = WIDTH - MARGIN
+    );
+    for i in 0..=4 {
+        let ratio = i as f64 / 4.0;
+        let x = x_min + ratio * (x_max - x_min);
+        let y = ratio * y_max;
+
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle" font-size="11">{}</text>"#,
+            x_pos(x),
+            HEIGHT - MARGIN + 15.0,
+            format_tick(x)
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="end" font-size="11">{}</text>"#,
+            MARGIN - 5.0,
+            y_pos(y) + 4.0,
+            format_tick(y)
+        );
+    }
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{}" text-anchor="middle" font-size="12">{}</text>"#,
+        WIDTH / 2.0,
+        HEIGHT - 15.0,
+        escape(plot.xlab)
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="15" y="{y}" text-anchor="middle" font-size="12" transform="rotate(-90 15 {y})">{}</text>"#,
+        escape(plot.ylab),
+        y = HEIGHT / 2.0
+    );
+
+    /* Series */
+    for (index, (name, points)) in series.iter().enumerate() {
+        let color = COLORS[index % COLORS.len()];
+        let path: Vec<String> = points
+            .iter()
+            .map(|(x, y)| format!("{:.2},{:.2}", x_pos(*x), y_pos(*y)))
+            .collect();
+
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"><title>{}</title></polyline>"#,
+            path.join(" "),
+            color,
+            escape(name)
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-size="11" fill="{}">{}</text>"#,
+            WIDTH - MARGIN + 5.0,
+            MARGIN + 14.0 * index as f64,
+            color,
+            escape(name)
+        );
+    }
+
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+/// Format a tick label
+fn format_tick(value: f64) -> String {
+    if value.fract() == 0.0 || value.abs() >= 100.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+/// Build summary table with scalar fields of all inputs
+fn summary(report: &report::Report) -> String {
+    let names: Vec<&str> = report
+        .field_names()
+        .into_iter()
+        .filter(|name| {
+            report
+                .inputs
+                .iter()
+                .filter_map(|i| i.get(name))
+                .all(|v| v.is_scalar())
+        })
+        .collect();
+
+    let mut table = String::new();
+    table.push_str("<table>\n<tr><th>type</th><th>file</th>");
+    for name in names.iter() {
+        let _ = write!(table, "<th>{}</th>", escape(name));
+    }
+    table.push_str("</tr>\n");
+
+    for input in report.inputs.iter() {
+        let _ = write!(
+            table,
+            "<tr><td>{}</td><td>{}</td>",
+            escape(&input.input_type),
+            escape(&input.file)
+        );
+        for name in names.iter() {
+            let value = input.get(name).map(|v| v.to_string()).unwrap_or_default();
+            let _ = write!(table, "<td>{}</td>", escape(&value));
+        }
+        table.push_str("</tr>\n");
+    }
+    table.push_str("</table>\n");
+
+    table
+}
+
+/// Write report as html in output
+pub fn write(output: &mut dyn std::io::Write, report: &report::Report) -> anyhow::Result<()> {
+    let mut input_types: Vec<&str> = Vec::new();
+    for input in report.inputs.iter() {
+        if !input_types.contains(&input.input_type.as_str()) {
+            input_types.push(&input.input_type);
+        }
+    }
+
+    writeln!(
+        output,
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>CrazyQC report</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; font-size: 12px; }}
+th, td {{ border: 1px solid #ccc; padding: 2px 6px; text-align: right; }}
+div.summary {{ overflow-x: auto; }}
+</style>
+</head>
+<body>
+<h1>CrazyQC report</h1>
+<p>crazyqc version {}, schema version {}</p>
+<h2>Summary</h2>
+<div class="summary">"#,
+        escape(&report.crazyqc_version),
+        report.schema_version
+    )?;
+    output.write_all(summary(report).as_bytes())?;
+    writeln!(output, "</div>")?;
+
+    for input_type in input_types {
+        writeln!(output, "<h2>{}</h2>", escape(input_type))?;
+
+        for plot in PLOTS.iter() {
+            let series: Vec<(String, Vec<(f64, f64)>)> = report
+                .inputs
+                .iter()
+                .filter(|i| i.input_type == input_type && i.file != "all")
+                .filter_map(|i| {
+                    let points = points(i.get(plot.field)?, plot.x_offset);
+                    if points.is_empty() {
+                        None
+                    } else {
+                        Some((i.file.clone(), points))
+                    }
+                })
+                .collect();
+
+            if !series.is_empty() {
+                output.write_all(line_chart(plot, &series).as_bytes())?;
+            }
+        }
+    }
+
+    writeln!(output, "</body>\n</html>")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    fn report() -> report::Report {
+        let mut report = report::Report::new();
+        report.inputs.push(report::Input {
+            input_type: "fastq".to_string(),
+            file: "<sample>.fastq".to_string(),
+            metrics: vec![
+                ("n".to_string(), metric::Value::Integer(2)),
+                (
+                    "gc_histogram".to_string(),
+                    metric::Value::List(vec![metric::Value::Integer(1), metric::Value::Integer(3)]),
+                ),
+                (
+                    "position_mean_quality".to_string(),
+                    metric::Value::List(vec![metric::Value::Float(f64::NAN)]),
+                ),
+            ],
+        });
+        report.inputs.push(report::Input {
+            input_type: "fastq".to_string(),
+            file: "all".to_string(),
+            metrics: vec![("n".to_string(), metric::Value::Integer(2))],
+        });
+
+        report
+    }
+
+    #[test]
+    fn escape_() {
+        assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+
+    #[test]
+    fn points_() {
+        assert_eq!(points(&metric::Value::Integer(1), 0.0), vec![]);
+        assert_eq!(
+            points(
+                &metric::Value::List(vec![
+                    metric::Value::Integer(1),
+                    metric::Value::Float(f64::NAN),
+                    metric::Value::Float(2.5)
+                ]),
+                1.0
+            ),
+            vec![(1.0, 1.0), (3.0, 2.5)]
+        );
+        assert_eq!(
+            points(
+                &metric::Value::List(vec![metric::Value::List(vec![
+                    metric::Value::Integer(150),
+                    metric::Value::Integer(2)
+                ])]),
+                0.0
+            ),
+            vec![(150.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn write_() {
+        let mut output = Vec::new();
+        write(&mut output, &report()).unwrap();
+
+        let html = String::from_utf8(output).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("<script"));
+        assert!(html.contains("<th>n</th>"));
+        assert!(!html.contains("<th>gc_histogram</th>"));
+        assert!(html.contains("<td>&lt;sample&gt;.fastq</td>"));
+        assert_eq!(html.matches("<svg").count(), 1);
+        assert!(html.contains("Per read GC content"));
+        assert!(!html.contains("Mean quality by position"));
+    }
+}
@@ -0,0 +1,62 @@
+//! Write report in json
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+use crate::report;
+
+/// Write report in json, NaN value are write as null
+pub fn write(output: &mut dyn std::io::Write, report: &report::Report) -> anyhow::Result<()> {
+    serde_json::to_writer(&mut *output, report)?;
+    writeln!(output)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use crate::metric;
+
+    #[test]
+    fn write_() {
+        let mut report = report::Report::new();
+        report.inputs.push(report::Input {
+            input_type: "fastq".to_string(),
+            file: "a.fastq".to_string(),
+            metrics: vec![
+                ("n".to_string(), metric::Value::Integer(1)),
+                ("at".to_string(), metric::Value::Float(f64::NAN)),
+                ("name".to_string(), metric::Value::Text("a".to_string())),
+                (
+                    "hist".to_string(),
+                    metric::Value::List(vec![metric::Value::Integer(1), metric::Value::Float(0.5)]),
+                ),
+            ],
+        });
+
+        let mut output = Vec::new();
+        write(&mut output, &report).unwrap();
+
+        let value: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "schema_version": report::SCHEMA_VERSION,
+                "crazyqc_version": env!("CARGO_PKG_VERSION"),
+                "inputs": [{
+                    "type": "fastq",
+                    "file": "a.fastq",
+                    "metrics": {"n": 1, "at": null, "name": "a", "hist": [1, 0.5]}
+                }]
+            })
+        );
+
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.find("\"n\"").unwrap() < text.find("\"hist\"").unwrap());
+    }
+}
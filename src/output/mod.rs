@@ -0,0 +1,30 @@
+//! Code to write report
+
+/* mod declaration section */
+pub mod csv;
+pub mod html;
+pub mod json;
+pub mod multiqc;
+pub mod table;
+
+/// Format of report
+#[derive(clap::ArgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// One line by input, list are write between bracket and separate by ;
+    Csv,
+
+    /// Json with a versioned schema
+    Json,
+}
+
+/// Write report in output with format
+pub fn write(
+    output: &mut dyn std::io::Write,
+    report: &crate::report::Report,
+    format: Format,
+) -> anyhow::Result<()> {
+    match format {
+        Format::Csv => csv::write(output, report),
+        Format::Json => json::write(output, report),
+    }
+}
@@ -0,0 +1,479 @@
+//! Write report as MultiQC custom content
+//!
+//! Each input type produce a general stats table and a plot by histogram or profile, each in a
+//! file with suffix `_mqc.json` found by MultiQC when directory is include in its search path.
+
+/* std use */
+
+/* crates use */
+use serde_json::json;
+
+/* project use */
+use crate::metric;
+use crate::report;
+
+/// Fields add in MultiQC general statistics table when present
+const GENERAL_STATS: [&str; 13] = [
+    "n",
+    "yield",
+    "mean_length",
+    "n50",
+    "gc",
+    "gc_mode",
+    "mean_quality",
+    "q30",
+    "flagstat_mapped_percent",
+    "coverage_mean",
+    "insert_size_median",
+    "target_on_percent",
+    "target_fold_80_penalty",
+];
+
+/// A plot build from a list field
+struct Plot {
+    field: &'static str,
+    plot_type: &'static str,
+    title: &'static str,
+    xlab: &'static str,
+    ylab: &'static str,
+    /// Value add to index of list to get x value, ignored for list of pair
+    x_offset: usize,
+}
+
+/// Plots write when field is present
+const PLOTS: [Plot; 8] = [
+    Plot {
+        field: "length_histogram",
+        plot_type: "linegraph",
+        title: "Read length distribution",
+        xlab: "Read length",
+        ylab: "Number of reads",
+        x_offset: 0,
+    },
+    Plot {
+        field: "gc_histogram",
+        plot_type: "linegraph",
+        title: "Per read GC content",
+        xlab: "GC %",
+        ylab: "Number of reads",
+        x_offset: 0,
+    },
+    Plot {
+        field: "quality_histogram",
+        plot_type: "bargraph",
+        title: "Base quality distribution",
+        xlab: "Phred score",
+        ylab: "Number of bases",
+        x_offset: 0,
+    },
+    Plot {
+        field: "mapq_histogram",
+        plot_type: "bargraph",
+        title: "Mapping quality distribution",
+        xlab: "Mapping quality",
+        ylab: "Number of reads",
+        x_offset: 0,
+    },
+    Plot {
+        field: "coverage_histogram",
+        plot_type: "linegraph",
+        title: "Depth of coverage distribution",
+        xlab: "Depth",
+        ylab: "Number of positions",
+        x_offset: 0,
+    },
+    Plot {
+        field: "insert_size_histogram",
+        plot_type: "linegraph",
+        title: "Insert size distribution",
+        xlab: "Insert size",
+        ylab: "Number of pairs",
+        x_offset: 0,
+    },
+    Plot {
+        field: "position_mean_quality",
+        plot_type: "linegraph",
+        title: "Mean quality by position",
+        xlab: "Position",
+        ylab: "Mean Phred score",
+        x_offset: 1,
+    },
+    Plot {
+        field: "position_n",
+        plot_type: "linegraph",
+        title: "N content by position",
+        xlab: "Position",
+        ylab: "N %",
+        x_offset: 1,
+    },
+];
+
+/// Get name of sample from file path, directory and sequence file extension are remove
+pub fn sample_name(path: &str) -> String {
+    let mut name = std::path::Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+
+    for extension in [".gz", ".bz2", ".xz", ".zst"] {
+        if let Some(stripped) = name.strip_suffix(extension) {
+            name = stripped.to_string();
+        }
+    }
+
+    for extension in [".fastq", ".fq", ".fasta", ".fa", ".bam", ".sam", ".cram"] {
+        if let Some(stripped) = name.strip_suffix(extension) {
+            name = stripped.to_string();
+        }
+    }
+
+    name
+}
+
+/// Get name of sample from file path with depth last parent directories
+fn qualified_name(path: &str, depth: usize) -> String {
+    let directories: Vec<String> = std::path::Path::new(path)
+        .parent()
+        .map(|parent| {
+            parent
+                .components()
+                .filter_map(|c| match c {
+                    std::path::Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut name = directories[directories.len() - depth.min(directories.len())..].join("/");
+    if !name.is_empty() {
+        name.push('/');
+    }
+    name.push_str(&sample_name(path));
+
+    name
+}
+
+/// Get unique name of samples from files path, when [sample_name] of files collide parent
+/// directories are add until names are unique
+///
+/// If names can't be unique (same path twice, ...) a warning is put in log and a suffix with
+/// occurrence number is add
+pub fn sample_names(paths: &[&str]) -> Vec<String> {
+    let mut depths = vec![0; paths.len()];
+
+    let mut names: Vec<String> = loop {
+        let names: Vec<String> = paths
+            .iter()
+            .zip(depths.iter())
+            .map(|(path, depth)| qualified_name(path, *depth))
+            .collect();
+
+        let mut changed = false;
+        for (index, name) in names.iter().enumerate() {
+            let collide = names.iter().filter(|other| *other == name).count() > 1;
+            if collide && qualified_name(paths[index], depths[index] + 1) != *name {
+                depths[index] += 1;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break names;
+        }
+    };
+
+    for index in 0..names.len() {
+        let occurrence = names[..index]
+            .iter()
+            .filter(|name| name.as_str() == names[index])
+            .count();
+        if occurrence != 0 {
+            log::warn!(
+                "Sample name {} of file {} isn't unique, MultiQC name is {}_{}",
+                names[index],
+                paths[index],
+                names[index],
+                occurrence + 1
+            );
+            names[index] = format!("{}_{}", names[index], occurrence + 1);
+        }
+    }
+
+    names
+}
+
+/// Convert a list value in a map of x value to y value
+fn series(
+    value: &metric::Value,
+    x_offset: usize,
+) -> Option<serde_json::Map<String, serde_json::Value>> {
+    let values = match value {
+        metric::Value::List(values) => values,
+        _ => return None,
+    };
+
+    let mut map = serde_json::Map::new();
+    for (index, value) in values.iter().enumerate() {
+        match value {
+            metric::Value::List(pair) if pair.len() == 2 => {
+                map.insert(pair[0].to_string(), json!(pair[1]));
+            }
+            _ => {
+                map.insert((index + x_offset).to_string(), json!(value));
+            }
+        }
+    }
+
+    Some(map)
+}
+
+/// Input of report with its unique sample name, aggregate inputs are ignored
+fn samples<'a>(report: &'a report::Report, input_type: &str) -> Vec<(String, &'a report::Input)> {
+    let inputs: Vec<&report::Input> = report
+        .inputs
+        .iter()
+        .filter(|i| i.input_type == input_type && i.file != "all")
+        .collect();
+
+    let paths: Vec<&str> = inputs.iter().map(|i| i.file.as_str()).collect();
+
+    sample_names(&paths).into_iter().zip(inputs).collect()
+}
+
+/// Write MultiQC custom content files of report in directory
+pub fn write<P>(directory: P, report: &report::Report) -> anyhow::Result<()>
+where
+    P: AsRef<std::path::Path>,
+{
+    let directory = directory.as_ref();
+    std::fs::create_dir_all(directory)?;
+
+    let mut input_types: Vec<&str> = Vec::new();
+    for input in report.inputs.iter() {
+        if !input_types.contains(&input.input_type.as_str()) {
+            input_types.push(&input.input_type);
+        }
+    }
+
+    for input_type in input_types {
+        write_general_stats(directory, report, input_type)?;
+
+        for plot in PLOTS.iter() {
+            write_plot(directory, report, input_type, plot)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_general_stats(
+    directory: &std::path::Path,
+    report: &report::Report,
+    input_type: &str,
+) -> anyhow::Result<()> {
+    let mut headers = serde_json::Map::new();
+    let mut data = serde_json::Map::new();
+
+    for (name, input) in samples(report, input_type) {
+        let mut row = serde_json::Map::new();
+
+        for field in GENERAL_STATS {
+            if let Some(value) = input.get(field) {
+                let key = format!("{}_{}", input_type, field);
+
+                headers.insert(
+                    key.clone(),
+                    json!({"title": field, "namespace": format!("crazyqc {}", input_type)}),
+                );
+                row.insert(key, json!(value));
+            }
+        }
+
+        data.insert(name, serde_json::Value::Object(row));
+    }
+
+    let content = json!({
+        "id": format!("crazyqc_{}_general_stats", input_type),
+        "plot_type": "generalstats",
+        "pconfig": headers,
+        "data": data,
+    });
+
+    write_content(
+        directory,
+        &format!("crazyqc_{}_general_stats_mqc.json", input_type),
+        &content,
+    )
+}
+
+fn write_plot(
+    directory: &std::path::Path,
+    report: &report::Report,
+    input_type: &str,
+    plot: &Plot,
+) -> anyhow::Result<()> {
+    let mut data = serde_json::Map::new();
+
+    for (name, input) in samples(report, input_type) {
+        if let Some(map) = input.get(plot.field).and_then(|v| series(v, plot.x_offset)) {
+            data.insert(name, serde_json::Value::Object(map));
+        }
+    }
+
+    if data.is_empty() {
+        return Ok(());
+    }
+
+    let id = format!("crazyqc_{}_{}", input_type, plot.field);
+    let content = json!({
+        "id": id,
+        "section_name": format!("CrazyQC {}: {}", input_type, plot.title),
+        "plot_type": plot.plot_type,
+        "pconfig": {
+            "id": format!("{}_plot", id),
+            "title": plot.title,
+            "xlab": plot.xlab,
+            "ylab": plot.ylab,
+        },
+        "data": data,
+    });
+
+    write_content(directory, &format!("{}_mqc.json", id), &content)
+}
+
+fn write_content(
+    directory: &std::path::Path,
+    name: &str,
+    content: &serde_json::Value,
+) -> anyhow::Result<()> {
+    let mut output = std::io::BufWriter::new(std::fs::File::create(directory.join(name))?);
+
+    serde_json::to_writer(&mut output, content)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    fn report() -> report::Report {
+        let mut report = report::Report::new();
+        report.inputs.push(report::Input {
+            input_type: "fastq".to_string(),
+            file: "data/sample1.fastq.gz".to_string(),
+            metrics: vec![
+                ("n".to_string(), metric::Value::Integer(2)),
+                ("other".to_string(), metric::Value::Float(0.5)),
+                (
+                    "gc_histogram".to_string(),
+                    metric::Value::List(vec![metric::Value::Integer(1), metric::Value::Integer(3)]),
+                ),
+                (
+                    "length_histogram".to_string(),
+                    metric::Value::List(vec![metric::Value::List(vec![
+                        metric::Value::Integer(150),
+                        metric::Value::Integer(2),
+                    ])]),
+                ),
+            ],
+        });
+        report.inputs.push(report::Input {
+            input_type: "fastq".to_string(),
+            file: "other/sample1.fastq".to_string(),
+            metrics: vec![("n".to_string(), metric::Value::Integer(3))],
+        });
+        report.inputs.push(report::Input {
+            input_type: "fastq".to_string(),
+            file: "all".to_string(),
+            metrics: vec![("n".to_string(), metric::Value::Integer(2))],
+        });
+
+        report
+    }
+
+    fn read(path: std::path::PathBuf) -> serde_json::Value {
+        serde_json::from_reader(std::fs::File::open(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn sample_name_() {
+        assert_eq!(sample_name("data/sample1.fastq.gz"), "sample1");
+        assert_eq!(sample_name("sample1.fq"), "sample1");
+        assert_eq!(sample_name("/a/b/sample1.bam"), "sample1");
+        assert_eq!(sample_name("sample1.txt"), "sample1.txt");
+    }
+
+    #[test]
+    fn sample_names_() {
+        assert_eq!(
+            sample_names(&["run1/s1.fastq", "run2/s1.fq.gz", "s2.fastq"]),
+            vec!["run1/s1", "run2/s1", "s2"]
+        );
+        assert_eq!(
+            sample_names(&["/data/a/run/s1.bam", "/data/b/run/s1.bam", "/data/b/s2.bam"]),
+            vec!["a/run/s1", "b/run/s1", "s2"]
+        );
+        assert_eq!(sample_names(&["s1.fq", "run/s1.fq"]), vec!["s1", "run/s1"]);
+        assert_eq!(
+            sample_names(&["a/s1.fq", "a/s1.fq", "-"]),
+            vec!["a/s1", "a/s1_2", "-"]
+        );
+    }
+
+    #[test]
+    fn series_() {
+        assert_eq!(series(&metric::Value::Integer(1), 0), None);
+
+        let map = series(
+            &metric::Value::List(vec![metric::Value::Integer(1), metric::Value::Integer(3)]),
+            1,
+        )
+        .unwrap();
+        assert_eq!(serde_json::Value::Object(map), json!({"1": 1, "2": 3}));
+    }
+
+    #[test]
+    fn write_() {
+        let directory = tempfile::tempdir().unwrap();
+
+        write(directory.path().join("multiqc"), &report()).unwrap();
+
+        let general = read(
+            directory
+                .path()
+                .join("multiqc")
+                .join("crazyqc_fastq_general_stats_mqc.json"),
+        );
+        assert_eq!(general["plot_type"], "generalstats");
+        assert_eq!(
+            general["data"],
+            json!({"data/sample1": {"fastq_n": 2}, "other/sample1": {"fastq_n": 3}})
+        );
+        assert_eq!(general["pconfig"]["fastq_n"]["title"], "n");
+
+        let gc = read(
+            directory
+                .path()
+                .join("multiqc")
+                .join("crazyqc_fastq_gc_histogram_mqc.json"),
+        );
+        assert_eq!(gc["plot_type"], "linegraph");
+        assert_eq!(gc["data"], json!({"data/sample1": {"0": 1, "1": 3}}));
+
+        let length = read(
+            directory
+                .path()
+                .join("multiqc")
+                .join("crazyqc_fastq_length_histogram_mqc.json"),
+        );
+        assert_eq!(length["data"], json!({"data/sample1": {"150": 2}}));
+
+        assert!(!directory
+            .path()
+            .join("multiqc")
+            .join("crazyqc_fastq_quality_histogram_mqc.json")
+            .exists());
+    }
+}
@@ -0,0 +1,114 @@
+//! Write a table field of each input in tsv
+
+/* std use */
+
+/* crates use */
+
+/* project use */
+use crate::metric;
+use crate::report;
+
+/// Columns of idxstats table
+pub const IDXSTATS: [&str; 5] = ["reference", "length", "mapped", "unmapped", "aligned_bases"];
+
+/// Columns of coverage by target table
+pub const TARGET_COVERAGE: [&str; 6] = [
+    "target",
+    "length",
+    "mean_depth",
+    "covered_1x",
+    "covered_10x",
+    "covered_30x",
+];
+
+/// Columns of insert size by read group table
+pub const INSERT_SIZE: [&str; 6] = [
+    "read_group",
+    "orientation",
+    "pairs",
+    "median",
+    "mad",
+    "mode",
+];
+
+/// Write rows of a table field of each input in tsv, one line by input and row
+pub fn write(
+    output: &mut dyn std::io::Write,
+    report: &report::Report,
+    field: &str,
+    columns: &[&str],
+) -> anyhow::Result<()> {
+    writeln!(output, "type\tfile\t{}", columns.join("\t"))?;
+
+    for input in report.inputs.iter() {
+        let rows = match input.get(field) {
+            Some(metric::Value::List(rows)) => rows,
+            _ => continue,
+        };
+
+        for row in rows {
+            if let metric::Value::List(values) = row {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+
+                writeln!(
+                    output,
+                    "{}\t{}\t{}",
+                    input.input_type,
+                    input.file,
+                    values.join("\t")
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn write_() {
+        let mut report = report::Report::new();
+        report.inputs.push(report::Input {
+            input_type: "fastq".to_string(),
+            file: "a.fastq".to_string(),
+            metrics: vec![("n".to_string(), metric::Value::Integer(1))],
+        });
+        report.inputs.push(report::Input {
+            input_type: "bam".to_string(),
+            file: "b.bam".to_string(),
+            metrics: vec![(
+                "idxstats".to_string(),
+                metric::Value::List(vec![
+                    metric::Value::List(vec![
+                        metric::Value::Text("chrM".to_string()),
+                        metric::Value::Integer(16),
+                        metric::Value::Integer(2),
+                        metric::Value::Integer(1),
+                        metric::Value::Integer(20),
+                    ]),
+                    metric::Value::List(vec![
+                        metric::Value::Text("*".to_string()),
+                        metric::Value::Integer(0),
+                        metric::Value::Integer(0),
+                        metric::Value::Integer(3),
+                        metric::Value::Integer(0),
+                    ]),
+                ]),
+            )],
+        });
+
+        let mut output = Vec::new();
+        write(&mut output, &report, "idxstats", &IDXSTATS).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "type\tfile\treference\tlength\tmapped\tunmapped\taligned_bases
+bam\tb.bam\tchrM\t16\t2\t1\t20
+bam\tb.bam\t*\t0\t0\t3\t0
+"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Report gather metrics of all inputs
+
+/* std use */
+
+/* crates use */
+use serde::ser::SerializeMap;
+
+/* project use */
+use crate::metric;
+
+/// Version of report schema, increase each time a field is rename or remove
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Metrics of one input file, or of all files of one type
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Input {
+    /// Type of input, fastq, bam, ...
+    #[serde(rename = "type")]
+    pub input_type: String,
+
+    /// Path of file, all for aggregate of all files of this type
+    pub file: String,
+
+    /// Fields produce by metrics
+    #[serde(serialize_with = "serialize_fields")]
+    pub metrics: Vec<metric::Field>,
+}
+
+impl Input {
+    /// Get value of a field by name
+    pub fn get(&self, name: &str) -> Option<&metric::Value> {
+        self.metrics
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Serialize fields as a map, order of fields is keep
+fn serialize_fields<S>(fields: &[metric::Field], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    let mut map = serializer.serialize_map(Some(fields.len()))?;
+    for (name, value) in fields {
+        map.serialize_entry(name, value)?;
+    }
+    map.end()
+}
+
+/// Metrics of all inputs
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Report {
+    /// Version of report schema
+    pub schema_version: u32,
+
+    /// Version of crazyqc use to produce report
+    pub crazyqc_version: String,
+
+    /// Metrics of each input
+    pub inputs: Vec<Input>,
+}
+
+impl Report {
+    /// Create an empty Report
+    pub fn new() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            crazyqc_version: env!("CARGO_PKG_VERSION").to_string(),
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Add one Input by file and an Input with aggregate value of all files
+    pub fn add_inputs<R>(
+        &mut self,
+        input_type: &str,
+        paths: &[String],
+        sets: &[metric::MetricSet<R>],
+        template: &metric::MetricSet<R>,
+    ) {
+        self.inputs
+            .extend(paths.iter().zip(sets).map(|(path, set)| Input {
+                input_type: input_type.to_string(),
+                file: path.to_string(),
+                metrics: set.finalize(),
+            }));
+
+        for input in &self.inputs[self.inputs.len() - paths.len()..] {
+            if let Some(metric::Value::Integer(nb_invalid)) = input.get("base_invalid") {
+                if *nb_invalid != 0 {
+                    log::warn!(
+                        "{} invalid characters in sequences of file {}",
+                        nb_invalid,
+                        input.file
+                    );
+                }
+            }
+        }
+
+        self.inputs.push(Input {
+            input_type: input_type.to_string(),
+            file: "all".to_string(),
+            metrics: metric::aggregate(sets, template).finalize(),
+        });
+    }
+
+    /// Name of all fields in order of first apparition
+    pub fn field_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+
+        for (name, _) in self.inputs.iter().flat_map(|i| i.metrics.iter()) {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+
+        names
+    }
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    use crate::input::fastq;
+
+    #[test]
+    fn add_inputs() {
+        let template = metric::MetricSet::new().with(metric::Composition::new());
+
+        let mut a = template.empty();
+        a.observe(&fastq::Record::new(b"ACGT".to_vec(), b"IIII".to_vec()));
+        let mut b = template.empty();
+        b.observe(&fastq::Record::new(b"AT".to_vec(), b"II".to_vec()));
+
+        let mut report = Report::new();
+        report.add_inputs(
+            "fastq",
+            &["a.fastq".to_string(), "b.fastq".to_string()],
+            &[a, b],
+            &template,
+        );
+
+        assert_eq!(report.schema_version, SCHEMA_VERSION);
+        assert_eq!(report.inputs.len(), 3);
+        assert_eq!(report.inputs[1].file, "b.fastq");
+        assert_eq!(report.inputs[2].file, "all");
+        assert_eq!(
+            report.inputs[2].get("at"),
+            Some(&metric::Value::Float(4.0 / 6.0 * 100.0))
+        );
+        assert_eq!(report.inputs[2].get("unknow"), None);
+        assert_eq!(
+            report.field_names(),
+            vec![
+                "at",
+                "gc",
+                "other",
+                "base_a",
+                "base_c",
+                "base_g",
+                "base_t",
+                "base_n",
+                "base_iupac",
+                "base_invalid"
+            ]
+        );
+    }
+}